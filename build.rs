@@ -0,0 +1,17 @@
+fn main() {
+    #[cfg(feature = "ffi")]
+    generate_header();
+}
+
+/// regenerates the C header of the `ffi` module, so it can't drift from the rust declarations.
+#[cfg(feature = "ffi")]
+fn generate_header() {
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    println!("cargo::rerun-if-changed=src/ffi.rs");
+    println!("cargo::rerun-if-changed=cbindgen.toml");
+
+    let config = cbindgen::Config::from_file(format!("{crate_dir}/cbindgen.toml")).expect("invalid cbindgen.toml");
+    cbindgen::generate_with_config(&crate_dir, config)
+        .expect("failed to generate include/xs_arena.h")
+        .write_to_file(format!("{crate_dir}/include/xs_arena.h"));
+}
@@ -0,0 +1,79 @@
+/* c interface of xs_arena_allocator, built with the `ffi` feature. generated by cbindgen, do not edit. */
+
+#ifndef XS_ARENA_H
+#define XS_ARENA_H
+
+#include <stdbool.h>
+#include <stddef.h>
+
+/*
+ opaque handle to an arena that is shared with C.
+ */
+typedef struct XsArena XsArena;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/*
+ creates an arena with the passed capacity in bytes. returns null if the memory pool can't be allocated.
+ */
+XsArena *xs_arena_create(size_t capacity);
+
+/*
+ allocates `size` bytes aligned to `align`, which has to be a power of two or 0 for the default alignment of 16.
+ returns null if the arena is full or the alignment is invalid.
+
+ # Safety
+ `arena` must be a live handle returned by `xs_arena_create` or `ArenaAllocator::into_raw_handle`.
+ */
+void *xs_arena_alloc(const XsArena *arena, size_t size, size_t align);
+
+/*
+ hands a block back to the arena. `size` and `align` have to be the ones passed to `xs_arena_alloc`.
+ freeing null does nothing.
+
+ # Safety
+ `arena` must be a live handle and `ptr` must have been allocated from it and must not be used afterwards.
+ */
+void xs_arena_free(const XsArena *arena, void *ptr, size_t size, size_t align);
+
+/*
+ resets the arena so its memory can be reused. returns false and leaves the arena untouched if blocks are
+ still allocated or the rust side still holds a clone of the allocator.
+
+ # Safety
+ `arena` must be a live handle that is not used by another thread at the same time.
+ */
+bool xs_arena_reset(XsArena *arena);
+
+/*
+ returns the amount of bytes that are currently used.
+
+ # Safety
+ `arena` must be a live handle.
+ */
+size_t xs_arena_used(const XsArena *arena);
+
+/*
+ returns the capacity of the arena in bytes.
+
+ # Safety
+ `arena` must be a live handle.
+ */
+size_t xs_arena_capacity(const XsArena *arena);
+
+/*
+ releases the handle. the arena is freed once the rust side dropped its clones of the allocator as well.
+ destroying null does nothing.
+
+ # Safety
+ `arena` must be null or a live handle, which must not be used afterwards.
+ */
+void xs_arena_destroy(XsArena *arena);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* XS_ARENA_H */
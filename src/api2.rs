@@ -0,0 +1,78 @@
+use core::{alloc::Layout, ptr::NonNull};
+
+use allocator_api2::alloc::{AllocError, Allocator};
+
+use crate::{Arena, ArenaAllocator};
+
+/// forwards the `allocator_api2` trait to the `core` implementation of the allocator.
+macro_rules! forward_allocator {
+    ($($ty:ty),+) => {$(
+        unsafe impl Allocator for $ty {
+            fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+                core::alloc::Allocator::allocate(self, layout).map_err(|_| AllocError)
+            }
+
+            fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+                core::alloc::Allocator::allocate_zeroed(self, layout).map_err(|_| AllocError)
+            }
+
+            unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+                unsafe { core::alloc::Allocator::deallocate(self, ptr, layout) }
+            }
+
+            unsafe fn grow(
+                &self,
+                ptr: NonNull<u8>,
+                old_layout: Layout,
+                new_layout: Layout,
+            ) -> Result<NonNull<[u8]>, AllocError> {
+                unsafe { core::alloc::Allocator::grow(self, ptr, old_layout, new_layout) }.map_err(|_| AllocError)
+            }
+
+            unsafe fn grow_zeroed(
+                &self,
+                ptr: NonNull<u8>,
+                old_layout: Layout,
+                new_layout: Layout,
+            ) -> Result<NonNull<[u8]>, AllocError> {
+                unsafe { core::alloc::Allocator::grow_zeroed(self, ptr, old_layout, new_layout) }
+                    .map_err(|_| AllocError)
+            }
+
+            unsafe fn shrink(
+                &self,
+                ptr: NonNull<u8>,
+                old_layout: Layout,
+                new_layout: Layout,
+            ) -> Result<NonNull<[u8]>, AllocError> {
+                unsafe { core::alloc::Allocator::shrink(self, ptr, old_layout, new_layout) }.map_err(|_| AllocError)
+            }
+        }
+    )+};
+}
+
+// `&Arena` is covered by the blanket implementation for references.
+forward_allocator!(Arena, ArenaAllocator);
+
+#[cfg(test)]
+mod test {
+    use allocator_api2::{boxed::Box, vec::Vec};
+
+    use super::*;
+
+    #[test]
+    fn collections_of_allocator_api2() {
+        let arena = Arena::new_aligned(256, 8);
+        let mut values = Vec::with_capacity_in(2, &arena);
+        values.extend([1u32, 2, 3]);
+        assert_eq!(values, [1, 2, 3]);
+        assert!(Vec::<u8, _>::new_in(&arena).try_reserve(1024).is_err());
+        drop(values);
+
+        let mut alloc = ArenaAllocator::new(arena);
+        let boxed = Box::new_in(7u64, alloc.clone());
+        assert_eq!(*boxed, 7);
+        drop(boxed);
+        assert!(alloc.try_reset());
+    }
+}
@@ -0,0 +1,117 @@
+//! rkyv integration, so payloads can be serialized with the arena as scratch space and accessed or deserialized
+//! in the arena without touching the global heap.
+
+use core::{alloc::Layout, ptr::NonNull};
+
+use rkyv::{
+    api::high::{HighDeserializer, HighValidator},
+    bytecheck::CheckBytes,
+    rancor::Source,
+    ser::Allocator,
+    Deserialize, Portable,
+};
+
+use crate::Arena;
+
+/// alignment the archived bytes are copied to by `Arena::access_archived`, the same as `rkyv::util::AlignedVec`.
+pub const ARCHIVE_ALIGN: usize = 16;
+
+/// uses the arena as scratch space for serializers, e.g. `rkyv::api::high::to_bytes_with_alloc(&value, &arena)`.
+/// scratch space is freed in reverse allocation order, so it is handed back to the arena with `free_last`.
+unsafe impl<E: Source> Allocator<E> for &Arena {
+    unsafe fn push_alloc(&mut self, layout: Layout) -> Result<NonNull<[u8]>, E> {
+        self.try_allocate(layout).map_err(E::new)
+    }
+
+    unsafe fn pop_alloc(&mut self, ptr: NonNull<u8>, layout: Layout) -> Result<(), E> {
+        unsafe { self.free_last(ptr, layout) };
+        Ok(())
+    }
+}
+
+impl Arena {
+    /// copies `bytes` into the arena, e.g. a payload that was just read from a connection, validates them and
+    /// returns the archived root. the archived value is used in place, so nothing is deserialized.
+    pub fn access_archived<T, E>(&self, bytes: &[u8]) -> Result<&T, E>
+    where
+        T: Portable + for<'a> CheckBytes<HighValidator<'a, E>>,
+        E: Source,
+    {
+        let layout = Layout::from_size_align(bytes.len(), ARCHIVE_ALIGN).map_err(E::new)?;
+        let block = self.try_allocate(layout).map_err(E::new)?.as_mut_ptr();
+        let copy = unsafe {
+            block.copy_from_nonoverlapping(bytes.as_ptr(), bytes.len());
+            core::slice::from_raw_parts(block, bytes.len())
+        };
+        rkyv::access::<T, E>(copy)
+    }
+
+    /// deserializes `archived` and moves the result into the arena, it is dropped when the arena is reset or dropped
+    /// the same as values allocated with `alloc_droppable`.
+    /// buffers owned by the deserialized value are still allocated by its own types, to keep them in the arena as
+    /// well use `access_archived` instead.
+    ///
+    /// # Panics
+    /// calls `handle_alloc_error` if the arena is exhausted.
+    #[allow(clippy::mut_from_ref)]
+    pub fn deserialize_archived<T, E>(&self, archived: &impl Deserialize<T, HighDeserializer<E>>) -> Result<&mut T, E>
+    where
+        T: Send,
+    {
+        Ok(self.alloc_droppable(rkyv::deserialize::<T, E>(archived)?))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use rkyv::{rancor::Error, Archive, Deserialize, Serialize};
+
+    use super::*;
+
+    #[derive(Archive, Serialize, Deserialize, Debug, PartialEq)]
+    struct Message {
+        id: u32,
+        name: String,
+        values: Vec<u64>,
+    }
+
+    fn message() -> Message {
+        Message {
+            id: 7,
+            name: "payload".into(),
+            values: vec![1, 2, 3],
+        }
+    }
+
+    #[test]
+    fn serializes_with_arena_scratch_space() {
+        let scratch = Arena::new(4096);
+        let bytes = rkyv::api::high::to_bytes_with_alloc::<_, Error>(&message(), &scratch).unwrap();
+
+        // the scratch space was handed back after serializing
+        assert_eq!(scratch.used_space(), 0);
+        let archived = rkyv::access::<ArchivedMessage, Error>(&bytes).unwrap();
+        assert_eq!(archived.name, "payload");
+    }
+
+    #[test]
+    fn accesses_and_deserializes_into_the_arena() {
+        let bytes = rkyv::to_bytes::<Error>(&message()).unwrap();
+        let arena = Arena::new(4096);
+
+        // payloads received into unaligned buffers are aligned by the copy
+        let mut unaligned = vec![0u8; bytes.len() + 1];
+        unaligned[1..].copy_from_slice(&bytes);
+        let archived = arena
+            .access_archived::<ArchivedMessage, Error>(&unaligned[1..])
+            .unwrap();
+        assert_eq!(archived.id, 7);
+        assert_eq!(archived.values.as_slice(), [1, 2, 3]);
+        assert!(arena.offset_of(NonNull::from(archived.values.as_slice()).cast()) < arena.used_space());
+
+        let deserialized = arena.deserialize_archived::<Message, Error>(archived).unwrap();
+        assert_eq!(*deserialized, message());
+
+        assert!(arena.access_archived::<ArchivedMessage, Error>(&[0xFF; 3]).is_err());
+    }
+}
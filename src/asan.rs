@@ -0,0 +1,80 @@
+//! manual poisoning of the memory pool for AddressSanitizer.
+//! the functions only do something if the crate is compiled with `-Zsanitizer=address`.
+
+#[cfg(sanitize = "address")]
+use core::ffi::c_void;
+
+use crate::Arena;
+
+#[cfg(sanitize = "address")]
+extern "C" {
+    fn __asan_poison_memory_region(addr: *const c_void, size: usize);
+    fn __asan_unpoison_memory_region(addr: *const c_void, size: usize);
+}
+
+/// marks `len` bytes starting at `ptr` as inaccessible, so that any access to them is reported.
+pub(crate) fn poison(ptr: *const u8, len: usize) {
+    #[cfg(sanitize = "address")]
+    unsafe {
+        __asan_poison_memory_region(ptr.cast(), len)
+    };
+    #[cfg(not(sanitize = "address"))]
+    let _ = (ptr, len);
+}
+
+/// marks `len` bytes starting at `ptr` as accessible again.
+pub(crate) fn unpoison(ptr: *const u8, len: usize) {
+    #[cfg(sanitize = "address")]
+    unsafe {
+        __asan_unpoison_memory_region(ptr.cast(), len)
+    };
+    #[cfg(not(sanitize = "address"))]
+    let _ = (ptr, len);
+}
+
+impl Arena {
+    /// poisons the whole memory pool. the exclusive borrow guarantees that nothing handed out is used anymore.
+    pub(crate) fn poison_pool(&mut self) {
+        poison(self.mem_pool.as_mut_ptr(), self.capacity());
+    }
+
+    /// unpoisons the whole memory pool, e.g. before it is handed back to its owner.
+    pub(crate) fn unpoison_pool(&mut self) {
+        unpoison(self.mem_pool.as_mut_ptr(), self.capacity());
+    }
+}
+
+#[cfg(all(test, sanitize = "address"))]
+mod test {
+    use core::alloc::Layout;
+
+    use super::*;
+
+    extern "C" {
+        fn __asan_address_is_poisoned(addr: *const c_void) -> i32;
+    }
+
+    fn is_poisoned(ptr: *const u8) -> bool {
+        unsafe { __asan_address_is_poisoned(ptr.cast()) != 0 }
+    }
+
+    #[test]
+    fn only_handed_out_memory_is_accessible() {
+        let mut arena = Arena::new(64);
+        let base = arena.mem_pool.as_mut_ptr();
+        assert!(is_poisoned(base));
+
+        let first = arena.get_next_mem_slice(Layout::new::<[u64; 2]>()).unwrap();
+        let second = arena.get_next_mem_slice(Layout::new::<[u64; 2]>()).unwrap();
+        assert!(!is_poisoned(first.as_mut_ptr()));
+        assert!(!is_poisoned(unsafe { first.as_mut_ptr().add(15) }));
+        assert!(is_poisoned(unsafe { second.as_mut_ptr().add(16) }));
+
+        // freed blocks are poisoned right away
+        assert!(unsafe { arena.free_last(second.as_non_null_ptr(), Layout::new::<[u64; 2]>()) });
+        assert!(is_poisoned(second.as_mut_ptr()));
+
+        arena.reset();
+        assert!(is_poisoned(first.as_mut_ptr()));
+    }
+}
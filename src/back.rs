@@ -0,0 +1,233 @@
+use alloc::sync::Arc;
+use core::{
+    alloc::{AllocError, Allocator, Layout},
+    ptr::NonNull,
+    sync::atomic::Ordering,
+};
+
+use crate::{Arena, ArenaAllocator, ArenaErrorKind};
+
+// allocations from the front bump `offset` up and allocations from the back bump `back` down.
+// both sides first move their own end and then check the other one, undoing the move if they overlap.
+// since all of these accesses are sequentially consistent, at least one of two overlapping allocations sees the other.
+
+impl Arena {
+    /// reserves a memory slice at the high end of the memory pool, which grows downwards until it meets the
+    /// allocations made from the low end. this lets one memory pool serve two lifetime classes,
+    /// e.g. long-lived data from the front and short-lived temporaries from the back.
+    /// back allocations are not rounded and are freed together with the front allocations once nothing is live.
+    pub fn alloc_back(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.live_allocations.fetch_add(1, Ordering::SeqCst);
+
+        let mut back_ends = self.back_ends.lock();
+        match self.back_bounds(layout) {
+            Ok((start, end)) => {
+                back_ends.push(end);
+                drop(back_ends);
+
+                #[cfg(feature = "asan")]
+                crate::asan::unpoison(unsafe { self.mem_pool.as_mut_ptr().add(start) }, end - start);
+
+                #[cfg(feature = "debug-fill")]
+                unsafe {
+                    self.mem_pool.as_mut_ptr().add(start).write_bytes(crate::DEBUG_FILL_ALLOCATED, end - start);
+                }
+
+                self.stats.allocations.fetch_add(1, Ordering::Relaxed);
+                self.stats.requested_bytes.fetch_add(layout.size(), Ordering::Relaxed);
+                unsafe { Ok(self.mem_pool.get_unchecked_mut(start..end)) }
+            }
+            Err(kind) => {
+                self.live_allocations.fetch_sub(1, Ordering::SeqCst);
+                self.last_error.store(kind.to_u8(), Ordering::Relaxed);
+                self.stats.failed_allocations.fetch_add(1, Ordering::Relaxed);
+                Err(AllocError)
+            }
+        }
+    }
+
+    /// hands the block of `ptr` back to the arena. if it is the most recent back allocation its space is reused.
+    ///
+    /// # Safety
+    /// `ptr` must have been allocated with `alloc_back` from this arena with `layout` and must not be used afterwards.
+    pub unsafe fn free_back(&self, ptr: NonNull<u8>, layout: Layout) -> bool {
+        #[cfg(feature = "asan")]
+        crate::asan::poison(ptr.as_ptr(), layout.size());
+
+        let start = self.offset_of(ptr);
+        let reclaimed = {
+            // the padding above the block is reclaimed as well, so the offset before the allocation is restored.
+            let mut back_ends = self.back_ends.lock();
+            match back_ends.last() {
+                Some(&end) if self.back.compare_exchange(start, end, Ordering::SeqCst, Ordering::SeqCst).is_ok() => {
+                    back_ends.pop();
+                    self.wasted_to_alignment.fetch_sub(end - start - layout.size(), Ordering::Relaxed);
+                    true
+                }
+                _ => false,
+            }
+        };
+        self.release(ptr);
+        reclaimed
+    }
+
+    /// returns whether an allocation from the front that ends at `end` doesn't overlap the back allocations.
+    pub(crate) fn front_fits(&self, end: usize) -> bool {
+        end <= self.back.load(Ordering::SeqCst)
+    }
+
+    fn back_bounds(&self, layout: Layout) -> Result<(usize, usize), ArenaErrorKind> {
+        let base = self.mem_pool.as_mut_ptr().addr();
+
+        loop {
+            let end = self.back.load(Ordering::Acquire);
+            let start = (base + end)
+                .checked_sub(layout.size())
+                .map(|addr| (addr & !(layout.align() - 1)).wrapping_sub(base))
+                .filter(|&start| start <= end)
+                .ok_or_else(|| self.exhaustion_kind(layout))?;
+
+            if start < self.offset.load(Ordering::SeqCst) {
+                return Err(self.exhaustion_kind(layout));
+            }
+
+            #[cfg(all(any(unix, windows), feature = "std"))]
+            self.commit_range(start, end)?;
+            // linear memory only grows at the end, so the whole pool has to exist for allocations from the back.
+            #[cfg(target_arch = "wasm32")]
+            self.ensure_committed(end)?;
+
+            if self
+                .back
+                .compare_exchange(end, start, Ordering::SeqCst, Ordering::SeqCst)
+                .is_err()
+            {
+                continue;
+            }
+
+            if self.offset.load(Ordering::SeqCst) > start {
+                let _ = self
+                    .back
+                    .compare_exchange(start, end, Ordering::SeqCst, Ordering::Relaxed);
+                return Err(ArenaErrorKind::OutOfSpace);
+            }
+
+            self.wasted_to_alignment.fetch_add(end - start - layout.size(), Ordering::Relaxed);
+            self.stats.consumed_bytes.fetch_add(end - start, Ordering::Relaxed);
+            return Ok((start, end));
+        }
+    }
+
+    /// makes `start..end` accessible if the arena commits its memory pool in steps from the front.
+    #[cfg(all(any(unix, windows), feature = "std"))]
+    fn commit_range(&self, start: usize, end: usize) -> Result<(), ArenaErrorKind> {
+        let crate::Backing::Mmap { commit: Some(commit), .. } = &self.backing else {
+            return Ok(());
+        };
+
+        let page_size = crate::sys::page_size();
+        let start = (start / page_size * page_size).max(commit.committed.load(Ordering::Acquire));
+        let end = end.next_multiple_of(page_size).min(self.capacity());
+        if start >= end {
+            return Ok(());
+        }
+
+        unsafe { crate::sys::commit(self.mem_pool.as_mut_ptr().add(start), end - start) }
+            .map_err(|_| ArenaErrorKind::OutOfSpace)
+    }
+}
+
+/// allocator for the high end of the memory pool of an `ArenaAllocator`. see `Arena::alloc_back`.
+#[derive(Clone)]
+pub struct BackArenaAllocator {
+    arena: Arc<Arena>,
+}
+
+impl ArenaAllocator {
+    /// returns an allocator that allocates from the high end of the same arena.
+    pub fn back(&self) -> BackArenaAllocator {
+        BackArenaAllocator {
+            arena: self.arena.clone(),
+        }
+    }
+}
+
+unsafe impl Allocator for BackArenaAllocator {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.arena.alloc_back(layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        unsafe { self.arena.free_back(ptr, layout) };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    #[test]
+    fn both_ends_meet_in_the_middle() {
+        let arena = Arena::new_aligned(64, 8);
+        let front = arena.get_next_mem_slice(Layout::new::<[u8; 20]>()).unwrap();
+        let back = arena.alloc_back(Layout::new::<u64>()).unwrap();
+        assert_eq!(back.len(), 8);
+        assert_eq!(back.as_mut_ptr().align_offset(8), 0);
+        assert!(back.as_mut_ptr().addr() >= front.as_mut_ptr().addr() + 20);
+        assert!(arena.available_space() <= 64 - 28);
+
+        // the two ends can't overlap
+        let available = arena.available_space();
+        assert!(arena.alloc_back(Layout::array::<u8>(available + 1).unwrap()).is_err());
+        assert!(arena.get_next_mem_slice(Layout::array::<u8>(available + 1).unwrap()).is_err());
+        arena.alloc_back(Layout::array::<u8>(available).unwrap()).unwrap();
+        assert_eq!(arena.available_space(), 0);
+    }
+
+    #[test]
+    fn back_allocator() {
+        let arena_alloc = ArenaAllocator::new(Arena::new_aligned(128, 4));
+        let back = arena_alloc.back();
+
+        let long_lived = Vec::<u32, _>::with_capacity_in(4, arena_alloc.clone());
+        for _ in 0..100 {
+            let mut scratch = Vec::<u8, _>::with_capacity_in(64, back.clone());
+            scratch.extend(0..64);
+        }
+        assert_eq!(arena_alloc.arena.used_space(), 16);
+
+        drop(long_lived);
+        assert_eq!(arena_alloc.arena.available_space(), 128);
+    }
+
+    #[test]
+    fn free_back_reclaims_the_padding() {
+        let arena = Arena::new_aligned(64, 8);
+        arena.get_next_mem_slice(Layout::new::<u32>()).unwrap();
+        let available = arena.available_space();
+
+        let byte = arena.alloc_back(Layout::new::<u8>()).unwrap();
+        let word = arena.alloc_back(Layout::new::<u64>()).unwrap();
+        assert_eq!(word.len(), 15);
+        unsafe {
+            assert!(arena.free_back(word.as_non_null_ptr(), Layout::new::<u64>()));
+            assert_eq!(arena.wasted_to_alignment(), 0);
+            assert!(arena.free_back(byte.as_non_null_ptr(), Layout::new::<u8>()));
+        }
+        assert_eq!(arena.available_space(), available);
+    }
+
+    #[test]
+    fn rollback_restores_the_back() {
+        let arena = Arena::new(64);
+        arena.get_next_mem_slice(Layout::new::<u32>()).unwrap();
+        let available = arena.available_space();
+
+        let mark = arena.mark();
+        arena.alloc_back(Layout::new::<[u8; 16]>()).unwrap();
+        unsafe { assert!(arena.rollback_to(mark)) };
+        assert_eq!(arena.available_space(), available);
+    }
+}
@@ -0,0 +1,118 @@
+use core::{alloc::Layout, iter::FusedIterator, ptr::NonNull};
+
+use crate::{Arena, ArenaError};
+
+impl Arena {
+    /// allocates `count` blocks for `layout` with a single bump of the offset, e.g. to hand out the nodes of a tree
+    /// or the particles of a frame without contending on the offset once per block. the blocks are consecutive,
+    /// each counts as its own allocation and can be deallocated on its own.
+    /// either all blocks are allocated or none.
+    #[cfg_attr(feature = "profile", track_caller)]
+    pub fn allocate_batch(&self, layout: Layout, count: usize) -> Result<AllocationBatch, ArenaError> {
+        if count == 0 {
+            return Ok(AllocationBatch {
+                next: NonNull::dangling(),
+                stride: 0,
+                size: 0,
+                remaining: 0,
+            });
+        }
+
+        let (start, end, stride) = self.allocate_blocks(layout, count)?;
+        Ok(AllocationBatch {
+            next: unsafe { self.base().add(start) },
+            stride,
+            size: end - start - stride * (count - 1),
+            remaining: count,
+        })
+    }
+}
+
+/// iterator over the blocks allocated by `Arena::allocate_batch`.
+pub struct AllocationBatch {
+    next: NonNull<u8>,
+    stride: usize,
+    size: usize,
+    remaining: usize,
+}
+
+impl Iterator for AllocationBatch {
+    type Item = NonNull<[u8]>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+
+        let block = NonNull::slice_from_raw_parts(self.next, self.size);
+        self.remaining -= 1;
+        if self.remaining > 0 {
+            self.next = unsafe { self.next.add(self.stride) };
+        }
+        Some(block)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl ExactSizeIterator for AllocationBatch {}
+
+impl FusedIterator for AllocationBatch {}
+
+#[cfg(test)]
+mod test {
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::ArenaErrorKind;
+
+    #[test]
+    fn batch_of_disjoint_blocks() {
+        let arena = Arena::new(1024);
+        arena.get_next_mem_slice(Layout::new::<u8>()).unwrap();
+
+        let layout = Layout::from_size_align(12, 8).unwrap();
+        let blocks = arena.allocate_batch(layout, 10).unwrap();
+        assert_eq!(blocks.len(), 10);
+        let blocks: Vec<_> = blocks.collect();
+        for pair in blocks.windows(2) {
+            assert_eq!(pair[1].as_mut_ptr().addr() - pair[0].as_mut_ptr().addr(), 16);
+        }
+        for (index, block) in blocks.iter().enumerate() {
+            assert!(block.len() >= 12);
+            assert_eq!(block.as_mut_ptr().addr() % 8, 0);
+            unsafe { block.as_mut_ptr().write_bytes(index as u8, block.len()) };
+        }
+        assert_eq!(arena.stats().allocations, 11);
+
+        // the blocks are deallocated one by one, only the last one is reclaimed because of the padding between them
+        let used = arena.used_space();
+        for block in blocks.iter().rev() {
+            unsafe { arena.free_last(block.as_non_null_ptr(), layout) };
+        }
+        assert_eq!(arena.used_space(), used - 12);
+        assert_eq!(arena.live_allocations(), 1);
+    }
+
+    #[test]
+    fn batches_are_all_or_nothing() {
+        let arena = Arena::new_aligned(64, 8);
+        let error = arena.allocate_batch(Layout::new::<u64>(), 9).err().unwrap();
+        assert_eq!(error.kind, ArenaErrorKind::OutOfSpace);
+        assert_eq!(
+            arena
+                .allocate_batch(Layout::new::<u64>(), usize::MAX)
+                .err()
+                .unwrap()
+                .kind,
+            ArenaErrorKind::Overflow
+        );
+        assert_eq!(arena.used_space(), 0);
+
+        assert_eq!(arena.allocate_batch(Layout::new::<u64>(), 0).unwrap().count(), 0);
+        assert_eq!(arena.allocate_batch(Layout::new::<u64>(), 8).unwrap().count(), 8);
+        assert_eq!(arena.available_space(), 0);
+    }
+}
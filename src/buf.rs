@@ -0,0 +1,170 @@
+use alloc::vec::Vec;
+use core::ops::{Deref, DerefMut};
+
+use bytes::{buf::UninitSlice, BufMut, Bytes};
+
+use crate::ArenaAllocator;
+
+/// growable byte buffer in an arena that implements `bytes::BufMut`, e.g. for the socket buffers of a connection
+/// that owns its arena. the buffer holds a handle to the arena, so it can be moved to other tasks.
+/// it grows in place as long as nothing else was allocated after it.
+pub struct ArenaBuf {
+    bytes: Vec<u8, ArenaAllocator>,
+}
+
+impl ArenaBuf {
+    pub fn new_in(alloc: ArenaAllocator) -> Self {
+        Self {
+            bytes: Vec::new_in(alloc),
+        }
+    }
+
+    /// # Panics
+    /// calls `handle_alloc_error` if the arena is exhausted.
+    pub fn with_capacity_in(capacity: usize, alloc: ArenaAllocator) -> Self {
+        Self {
+            bytes: Vec::with_capacity_in(capacity, alloc),
+        }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.bytes.capacity()
+    }
+
+    pub fn clear(&mut self) {
+        self.bytes.clear();
+    }
+
+    /// turns the written bytes into a `Bytes` without copying them. clones and slices of it share the block,
+    /// which is handed back to the arena when the last of them is dropped.
+    pub fn freeze(self) -> Bytes {
+        Bytes::from_owner(self)
+    }
+
+    /// returns the written bytes without copying them.
+    pub fn into_vec(self) -> Vec<u8, ArenaAllocator> {
+        self.bytes
+    }
+}
+
+impl Deref for ArenaBuf {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        &self.bytes
+    }
+}
+
+impl DerefMut for ArenaBuf {
+    fn deref_mut(&mut self) -> &mut [u8] {
+        &mut self.bytes
+    }
+}
+
+impl AsRef<[u8]> for ArenaBuf {
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+unsafe impl BufMut for ArenaBuf {
+    fn remaining_mut(&self) -> usize {
+        isize::MAX as usize - self.bytes.len()
+    }
+
+    unsafe fn advance_mut(&mut self, cnt: usize) {
+        let len = self.bytes.len() + cnt;
+        assert!(len <= self.bytes.capacity(), "advanced past the capacity of the buffer");
+        unsafe { self.bytes.set_len(len) };
+    }
+
+    fn chunk_mut(&mut self) -> &mut UninitSlice {
+        // the same growth step as `BufMut` for `Vec`.
+        if self.bytes.capacity() == self.bytes.len() {
+            self.bytes.reserve(64);
+        }
+        let spare = self.bytes.spare_capacity_mut();
+        unsafe { UninitSlice::from_raw_parts_mut(spare.as_mut_ptr().cast(), spare.len()) }
+    }
+
+    fn put_slice(&mut self, src: &[u8]) {
+        self.bytes.extend_from_slice(src);
+    }
+}
+
+impl ArenaAllocator {
+    /// returns an empty byte buffer that allocates from the arena.
+    pub fn buf(&self) -> ArenaBuf {
+        ArenaBuf::new_in(self.clone())
+    }
+
+    /// returns an empty byte buffer with space for `capacity` bytes in the arena.
+    ///
+    /// # Panics
+    /// calls `handle_alloc_error` if the arena is exhausted.
+    pub fn buf_with_capacity(&self, capacity: usize) -> ArenaBuf {
+        ArenaBuf::with_capacity_in(capacity, self.clone())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::slice;
+    use std::io::Read;
+
+    use bytes::Buf;
+
+    use super::*;
+    use crate::Arena;
+
+    #[test]
+    fn frozen_buffers_share_the_block() {
+        let mut alloc = ArenaAllocator::new(Arena::new_aligned(1024, 8));
+        let mut buf = alloc.buf_with_capacity(4);
+        buf.put_u16(0x0102);
+        buf.put_slice(b"header");
+        buf.put_bytes(0, 100);
+        assert_eq!(&buf[..8], b"\x01\x02header");
+
+        // the last block of the arena grows in place
+        assert_eq!(alloc.stats().allocations, 1);
+
+        let frozen = buf.freeze();
+        let mut header = frozen.slice(2..8);
+        let copy = frozen.clone();
+        drop(frozen);
+        assert_eq!(copy.len(), 108);
+        assert_eq!(header.get_u8(), b'h');
+        assert_eq!(&header[..], b"eader");
+
+        assert!(!alloc.try_reset());
+        drop((header, copy));
+        assert!(alloc.try_reset());
+    }
+
+    #[test]
+    fn reads_into_the_spare_capacity() {
+        let alloc = ArenaAllocator::new(Arena::new_aligned(1024, 8));
+        let payload: Vec<u8> = (0..200u8).collect();
+        let mut socket = &payload[..];
+
+        let mut buf = alloc.buf();
+        loop {
+            let chunk = buf.chunk_mut();
+            let len = chunk.len();
+            let target = unsafe { slice::from_raw_parts_mut(chunk.as_mut_ptr(), len) };
+            target.fill(0);
+            let read = socket.read(target).unwrap();
+            if read == 0 {
+                break;
+            }
+            unsafe { buf.advance_mut(read) };
+        }
+        assert_eq!(&buf[..], &payload[..]);
+        assert!(buf.capacity() >= 200);
+
+        buf.clear();
+        assert!(buf.is_empty());
+        assert!(buf.into_vec().is_empty());
+    }
+}
@@ -0,0 +1,282 @@
+use core::alloc::Layout;
+
+#[cfg(feature = "std")]
+use crate::{GrowableArena, GrowthPolicy};
+#[cfg(all(any(unix, windows), feature = "std"))]
+use crate::{MmapOptions, NumaPolicy};
+use crate::{Arena, ArenaError, ArenaErrorKind, LeakPolicy, RoundingPolicy};
+
+/// capacity of arenas built by an `ArenaBuilder` that wasn't given one.
+pub const DEFAULT_CAPACITY: usize = 64 * 1024;
+
+/// collects the options of an arena before creating it, so `Arena::new` can stay simple.
+/// apart from the capacity every option defaults to the behavior of `Arena::new`.
+#[derive(Clone, Debug)]
+pub struct ArenaBuilder {
+    capacity: usize,
+    align: usize,
+    zeroed: bool,
+    rounding: RoundingPolicy,
+    leak_policy: LeakPolicy,
+    #[cfg(all(any(unix, windows), feature = "std"))]
+    mmap: Option<MmapOptions>,
+    #[cfg(feature = "std")]
+    growth: Option<GrowthPolicy>,
+}
+
+impl Default for ArenaBuilder {
+    fn default() -> Self {
+        Self {
+            capacity: DEFAULT_CAPACITY,
+            align: 1,
+            zeroed: false,
+            rounding: RoundingPolicy::Off,
+            leak_policy: LeakPolicy::Ignore,
+            #[cfg(all(any(unix, windows), feature = "std"))]
+            mmap: None,
+            #[cfg(feature = "std")]
+            growth: None,
+        }
+    }
+}
+
+impl ArenaBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// sets the capacity of the memory pool in bytes. defaults to `DEFAULT_CAPACITY`.
+    pub fn capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity;
+        self
+    }
+
+    /// aligns the start of the memory pool to `align` bytes, which has to be a power of two. see `Arena::new_aligned`.
+    pub fn align(mut self, align: usize) -> Self {
+        self.align = align;
+        self
+    }
+
+    /// zeroes the whole memory pool up front instead of handing out uninitialized memory.
+    /// mapped memory pools are always zeroed by the operating system.
+    pub fn zeroed(mut self, zeroed: bool) -> Self {
+        self.zeroed = zeroed;
+        self
+    }
+
+    /// sets the policy used to round up the size of every allocation. see `Arena::with_rounding`.
+    pub fn rounding(mut self, rounding: RoundingPolicy) -> Self {
+        self.rounding = rounding;
+        self
+    }
+
+    /// sets what happens when the arena is reset or dropped with outstanding allocations. see `LeakPolicy`.
+    pub fn leak_policy(mut self, leak_policy: LeakPolicy) -> Self {
+        self.leak_policy = leak_policy;
+        self
+    }
+
+    /// maps the memory pool directly from the operating system instead of allocating it from the global allocator.
+    /// see `Arena::new_mmap_with`. an alignment set on the builder is applied on top of the options.
+    #[cfg(all(any(unix, windows), feature = "std"))]
+    pub fn mmap(mut self, options: MmapOptions) -> Self {
+        self.mmap = Some(options);
+        self
+    }
+
+    /// binds the pages of the memory pool to NUMA node `node`. implies a mapped memory pool, see `MmapOptions::numa`.
+    #[cfg(all(any(unix, windows), feature = "std"))]
+    pub fn numa_node(self, node: usize) -> Self {
+        self.numa(NumaPolicy::Node(node))
+    }
+
+    /// interleaves the pages of the memory pool over all NUMA nodes. implies a mapped memory pool.
+    #[cfg(all(any(unix, windows), feature = "std"))]
+    pub fn numa_interleave(self) -> Self {
+        self.numa(NumaPolicy::Interleave)
+    }
+
+    /// links additional chunks once the memory pool is exhausted instead of failing, so `build` returns a
+    /// `GrowableArena`. the chunks double in size unless `growth_policy` is set.
+    #[cfg(feature = "std")]
+    pub fn growable(mut self, growable: bool) -> Self {
+        self.growth = growable.then(|| self.growth.unwrap_or(GrowthPolicy::Doubling));
+        self
+    }
+
+    /// sets the size of the chunks that are linked once the memory pool is exhausted. implies `growable(true)`.
+    #[cfg(feature = "std")]
+    pub fn growth_policy(mut self, policy: GrowthPolicy) -> Self {
+        self.growth = Some(policy);
+        self
+    }
+
+    #[cfg(all(any(unix, windows), feature = "std"))]
+    fn numa(mut self, policy: NumaPolicy) -> Self {
+        self.mmap = Some(self.mmap.take().unwrap_or_default().numa(policy));
+        self
+    }
+
+    /// creates the arena. fails if the alignment is invalid or the memory pool can't be allocated.
+    /// the first chunk of a growable arena is built with the options of the builder, the later chunks are plain arenas
+    /// like the ones created by `Arena::new`.
+    pub fn build(self) -> Result<BuiltArena, ArenaError> {
+        let arena = self.build_fixed()?;
+        #[cfg(feature = "std")]
+        if let Some(policy) = self.growth {
+            return Ok(BuiltArena::Growable(GrowableArena::from_arena(arena, policy)));
+        }
+        Ok(BuiltArena::Fixed(arena))
+    }
+
+    fn build_fixed(&self) -> Result<Arena, ArenaError> {
+        let error = |kind| ArenaError {
+            layout: Layout::array::<u8>(self.capacity).unwrap_or(Layout::new::<u8>()),
+            available: 0,
+            kind,
+        };
+
+        if !self.align.is_power_of_two() {
+            return Err(error(ArenaErrorKind::AlignmentUnsatisfiable));
+        }
+
+        #[cfg(all(any(unix, windows), feature = "std"))]
+        if let Some(options) = self.mmap.clone() {
+            let align = self.align.max(options.align);
+            let options = options.align(align);
+            let arena = Arena::new_mmap_with(self.capacity, options).map_err(|err| error(ArenaErrorKind::from(err)))?;
+            return Ok(self.configure(arena));
+        }
+
+        let arena = if self.align > 1 {
+            Arena::try_new_aligned(self.capacity, self.align)?
+        } else {
+            Arena::try_new(self.capacity)?
+        };
+
+        if self.zeroed {
+            let base = arena.mem_pool.as_mut_ptr();
+            #[cfg(feature = "asan")]
+            crate::asan::unpoison(base, arena.capacity());
+            unsafe { base.write_bytes(0, arena.capacity()) };
+            #[cfg(feature = "asan")]
+            crate::asan::poison(base, arena.capacity());
+        }
+        Ok(self.configure(arena))
+    }
+
+    fn configure(&self, arena: Arena) -> Arena {
+        arena.with_rounding(self.rounding).with_leak_policy(self.leak_policy)
+    }
+}
+
+/// the arena created by `ArenaBuilder::build`, which is growable if the builder was.
+// it is unpacked right after building, so the size of the fixed arena doesn't matter.
+#[allow(clippy::large_enum_variant)]
+pub enum BuiltArena {
+    Fixed(Arena),
+    #[cfg(feature = "std")]
+    Growable(GrowableArena),
+}
+
+impl BuiltArena {
+    /// returns the arena if the builder wasn't growable.
+    pub fn into_fixed(self) -> Option<Arena> {
+        match self {
+            BuiltArena::Fixed(arena) => Some(arena),
+            #[cfg(feature = "std")]
+            BuiltArena::Growable(_) => None,
+        }
+    }
+
+    /// returns the arena if the builder was growable.
+    #[cfg(feature = "std")]
+    pub fn into_growable(self) -> Option<GrowableArena> {
+        match self {
+            BuiltArena::Growable(arena) => Some(arena),
+            BuiltArena::Fixed(_) => None,
+        }
+    }
+}
+
+impl Arena {
+    /// returns a builder for arenas with non-default options.
+    pub fn builder() -> ArenaBuilder {
+        ArenaBuilder::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn builds_configured_arenas() {
+        let arena = Arena::builder().capacity(1024).align(64).zeroed(true).build().unwrap().into_fixed().unwrap();
+        assert_eq!(arena.capacity(), 1024);
+        assert_eq!(arena.mem_pool.as_mut_ptr().align_offset(64), 0);
+        assert!(unsafe { arena.mem_pool.as_ref() }.iter().all(|&byte| byte == 0));
+
+        let arena = ArenaBuilder::new().rounding(RoundingPolicy::NextPowerOfTwo).build().unwrap().into_fixed().unwrap();
+        assert_eq!(arena.capacity(), DEFAULT_CAPACITY);
+        assert_eq!(arena.get_next_mem_slice(Layout::new::<[u8; 5]>()).unwrap().len(), 8);
+
+        let err = Arena::builder().align(3).build().err().unwrap();
+        assert_eq!(err.kind, ArenaErrorKind::AlignmentUnsatisfiable);
+    }
+
+    #[cfg(all(any(unix, windows), feature = "std"))]
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn builds_mapped_arenas() {
+        let arena = Arena::builder()
+            .capacity(1 << 20)
+            .align(1 << 20)
+            .mmap(MmapOptions::new())
+            .build()
+            .unwrap()
+            .into_fixed()
+            .unwrap();
+        assert_eq!(arena.mem_pool.as_mut_ptr().align_offset(1 << 20), 0);
+        assert_eq!(arena.requested_capacity(), 1 << 20);
+
+        // the address space is smaller than the pool.
+        let err = Arena::builder().capacity(1 << 60).mmap(MmapOptions::new()).build().err().unwrap();
+        #[cfg(target_os = "linux")]
+        assert_eq!(err.kind, ArenaErrorKind::Os(libc::ENOMEM));
+        assert!(matches!(err.kind, ArenaErrorKind::Os(_)));
+    }
+
+    #[cfg(all(target_os = "linux", feature = "std"))]
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn builds_numa_arenas() {
+        let arena = Arena::builder().capacity(4096).numa_node(0).build().unwrap().into_fixed().unwrap();
+        assert!(matches!(arena.backing, crate::Backing::Mmap { .. }));
+        assert!(Arena::builder().numa_interleave().build().is_ok());
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn builds_growable_arenas() {
+        let arena = Arena::builder().capacity(64).growable(true).build().unwrap().into_growable().unwrap();
+        arena.get_next_mem_slice(Layout::new::<[u8; 64]>()).unwrap();
+        arena.get_next_mem_slice(Layout::new::<[u8; 64]>()).unwrap();
+        assert_eq!(arena.chunk_count(), 2);
+        assert_eq!(arena.capacity(), 64 + 128);
+
+        let arena = Arena::builder()
+            .capacity(64)
+            .growth_policy(GrowthPolicy::Fixed(32))
+            .build()
+            .unwrap()
+            .into_growable()
+            .unwrap();
+        arena.get_next_mem_slice(Layout::new::<[u8; 64]>()).unwrap();
+        arena.get_next_mem_slice(Layout::new::<[u8; 8]>()).unwrap();
+        assert_eq!(arena.capacity(), 64 + 32);
+
+        let builder = Arena::builder().growth_policy(GrowthPolicy::Fixed(32)).growable(false);
+        assert!(builder.build().unwrap().into_fixed().is_some());
+    }
+}
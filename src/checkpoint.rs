@@ -0,0 +1,355 @@
+use alloc::vec::Vec;
+use core::{
+    mem,
+    ops::{Deref, DerefMut},
+    sync::atomic::Ordering,
+};
+
+use crate::Arena;
+
+/// a position inside an arena that can be rolled back to with `Arena::rollback_to`.
+/// marks can be nested. rolling back to a mark invalidates every mark that was created after it.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ArenaMark {
+    offset: usize,
+    back: usize,
+    wasted_to_alignment: usize,
+    live_allocations: usize,
+    epoch: usize,
+}
+
+/// history of the rollbacks of an arena, used to detect marks that were invalidated by rolling back below them.
+#[derive(Default)]
+pub(crate) struct RollbackLog {
+    /// the amount of rollbacks so far.
+    epoch: usize,
+    /// `(epoch, target offset)` of past rollbacks. a rollback is dropped from the log once a later rollback
+    /// rewinds at least as far, so the target offsets are strictly increasing.
+    minima: Vec<(usize, usize)>,
+}
+
+impl RollbackLog {
+    fn is_valid(&self, mark: &ArenaMark) -> bool {
+        self.furthest_since(mark.epoch).is_none_or(|target| target >= mark.offset)
+    }
+
+    pub(crate) fn epoch(&self) -> usize {
+        self.epoch
+    }
+
+    /// returns the lowest offset the arena was rolled back to since `epoch`.
+    pub(crate) fn furthest_since(&self, epoch: usize) -> Option<usize> {
+        // the first rollback recorded at or after the epoch rewound the furthest since then.
+        let index = self.minima.partition_point(|&(rollback_epoch, _)| rollback_epoch < epoch);
+        self.minima.get(index).map(|&(_, target)| target)
+    }
+
+    pub(crate) fn record(&mut self, target: usize) {
+        while self.minima.last().is_some_and(|&(_, last_target)| last_target >= target) {
+            self.minima.pop();
+        }
+        self.minima.push((self.epoch, target));
+        self.epoch += 1;
+    }
+}
+
+impl Arena {
+    /// returns a mark of the current position of the arena.
+    /// the arena never reclaims memory below a mark on its own, only `rollback_to` and `reset` do. marks can't be
+    /// released, so this lasts until the arena is rolled back or reset below it, use a `Checkpoint` otherwise.
+    pub fn mark(&self) -> ArenaMark {
+        let rollbacks = self.rollbacks.lock();
+        let offset = self.offset.load(Ordering::Acquire);
+        self.frozen.fetch_max(offset, Ordering::SeqCst);
+
+        ArenaMark {
+            offset,
+            back: self.back.load(Ordering::Acquire),
+            wasted_to_alignment: self.wasted_to_alignment.load(Ordering::Relaxed),
+            live_allocations: self.live_allocations.load(Ordering::Relaxed),
+            epoch: rollbacks.epoch,
+        }
+    }
+
+    /// rewinds the arena to the passed mark, which frees every allocation made after the mark was created.
+    /// returns false without changing the arena if the mark is invalid, because the arena was already rolled back
+    /// or reset below it.
+    ///
+    /// # Safety
+    /// allocations made after the mark must not be used anymore, this includes deallocating them.
+    /// no other thread may allocate from the arena during the rollback.
+    pub unsafe fn rollback_to(&self, mark: ArenaMark) -> bool {
+        let mut rollbacks = self.rollbacks.lock();
+
+        if !rollbacks.is_valid(&mark) || self.offset.load(Ordering::Acquire) < mark.offset {
+            return false;
+        }
+
+        self.rewind(&mark);
+        rollbacks.record(mark.offset);
+        true
+    }
+
+    /// runs `f` with the arena and rewinds it to where it was on entry once `f` returns or unwinds, e.g. for the
+    /// allocations of one frame. the result of `f` can't borrow the arena, so no allocation escapes the closure.
+    /// `&Arena` implements `Allocator`, so collections can be created inside with `Vec::new_in(arena)`.
+    ///
+    /// ```compile_fail
+    /// let mut arena = xs_arena_allocator::Arena::new(64);
+    /// let escaped: &mut u32 = arena.scope(|arena| arena.alloc(1u32));
+    /// ```
+    pub fn scope<R>(&mut self, f: impl FnOnce(&Arena) -> R) -> R {
+        let checkpoint = self.checkpoint();
+        f(&checkpoint)
+    }
+
+    fn rewind(&self, mark: &ArenaMark) {
+        self.run_drops(mark.offset);
+        self.record_peak();
+        self.offset.store(mark.offset, Ordering::SeqCst);
+        self.frozen.fetch_min(mark.offset, Ordering::SeqCst);
+        {
+            // allocations from the back made after the mark are below its back offset, unless the blocks from
+            // before the mark were freed since, whose space is then kept.
+            let mut back_ends = self.back_ends.lock();
+            if self.back.fetch_max(mark.back, Ordering::SeqCst) < mark.back {
+                back_ends.retain(|&end| end > mark.back);
+            }
+        }
+        self.wasted_to_alignment.store(mark.wasted_to_alignment, Ordering::Relaxed);
+        // allocations from before the mark may have been deallocated since, so the count can only go down.
+        self.live_allocations.fetch_min(mark.live_allocations, Ordering::SeqCst);
+
+        #[cfg(feature = "track-allocations")]
+        self.allocations.lock().retain(|allocation| allocation.offset < mark.offset);
+    }
+}
+
+/// records the state of an arena and rolls it back on drop unless `commit()` is called.
+/// this gives try/rollback semantics for speculative allocations, e.g. parsing that may fail and needs to be undone.
+/// the checkpoint borrows the arena exclusively, so every allocation made through it is discarded on rollback.
+pub struct Checkpoint<'a> {
+    arena: &'a mut Arena,
+    mark: ArenaMark,
+    /// the offset below which memory was kept before the checkpoint was created.
+    frozen: usize,
+}
+
+impl<'a> Checkpoint<'a> {
+    pub(crate) fn new(arena: &'a mut Arena) -> Self {
+        let frozen = *arena.frozen.get_mut();
+        Self {
+            mark: arena.mark(),
+            frozen,
+            arena,
+        }
+    }
+
+    /// keeps every allocation made since the checkpoint was created.
+    /// the arena reclaims memory below the checkpoint on its own again, unless a mark was created past it.
+    pub fn commit(mut self) {
+        self.release();
+        mem::forget(self);
+    }
+
+    /// discards every allocation made since the checkpoint was created.
+    pub fn rollback(self) {}
+
+    /// undoes the freeze of `Arena::mark`, as long as no other mark raised it further.
+    fn release(&mut self) {
+        if *self.arena.frozen.get_mut() == self.mark.offset {
+            *self.arena.frozen.get_mut() = self.frozen;
+        }
+    }
+}
+
+impl Deref for Checkpoint<'_> {
+    type Target = Arena;
+
+    fn deref(&self) -> &Self::Target {
+        self.arena
+    }
+}
+
+impl DerefMut for Checkpoint<'_> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.arena
+    }
+}
+
+impl Drop for Checkpoint<'_> {
+    fn drop(&mut self) {
+        // the arena might have been reset or rolled back below the checkpoint in the meantime.
+        if !self.arena.rollbacks.get_mut().is_valid(&self.mark) || *self.arena.offset.get_mut() < self.mark.offset {
+            return;
+        }
+
+        self.arena.rewind(&self.mark);
+        self.arena.rollbacks.get_mut().record(self.mark.offset);
+        self.release();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::alloc::{Allocator, Layout};
+
+    use super::*;
+    use crate::ArenaAllocator;
+
+    #[test]
+    fn commit() {
+        let mut arena = Arena::new_aligned(64, 8);
+        arena.get_next_mem_slice(Layout::new::<u32>()).unwrap();
+
+        let checkpoint = arena.checkpoint();
+        checkpoint.get_next_mem_slice(Layout::new::<[u8; 8]>()).unwrap();
+        checkpoint.commit();
+
+        assert_eq!(arena.used_space(), 12);
+        assert_eq!(arena.live_allocations(), 2);
+    }
+
+    #[test]
+    fn commit_releases_the_mark() {
+        let mut arena = Arena::new(64);
+        let block = arena.get_next_mem_slice(Layout::new::<u32>()).unwrap();
+        arena.checkpoint().commit();
+
+        // the last allocation is reclaimed as if there was no checkpoint
+        unsafe { arena.deallocate(block.as_non_null_ptr(), Layout::new::<u32>()) };
+        assert_eq!(arena.used_space(), 0);
+    }
+
+    #[test]
+    fn rollback() {
+        let mut arena = Arena::new_aligned(64, 8);
+        arena.get_next_mem_slice(Layout::new::<u32>()).unwrap();
+
+        {
+            let mut checkpoint = arena.checkpoint();
+            checkpoint.get_next_mem_slice(Layout::new::<u8>()).unwrap();
+            checkpoint.get_next_mem_slice(Layout::new::<u64>()).unwrap();
+
+            // nested checkpoints roll back independently
+            let nested = checkpoint.checkpoint();
+            nested.get_next_mem_slice(Layout::new::<[u8; 16]>()).unwrap();
+            nested.rollback();
+            assert_eq!(checkpoint.used_space(), 16);
+        }
+
+        assert_eq!(arena.used_space(), 4);
+        assert_eq!(arena.live_allocations(), 1);
+        assert_eq!(arena.wasted_to_alignment(), 0);
+    }
+
+    #[test]
+    fn nested_marks() {
+        let arena = Arena::new(64);
+        let outer = arena.mark();
+        arena.get_next_mem_slice(Layout::new::<[u8; 8]>()).unwrap();
+        let inner = arena.mark();
+        arena.get_next_mem_slice(Layout::new::<[u8; 8]>()).unwrap();
+
+        unsafe {
+            // rolling back in order keeps the outer mark valid
+            assert!(arena.rollback_to(inner));
+            assert_eq!(arena.used_space(), 8);
+            assert!(arena.rollback_to(inner));
+            assert!(arena.rollback_to(outer));
+            assert_eq!(arena.used_space(), 0);
+        }
+    }
+
+    #[test]
+    fn marks_rolled_back_out_of_order() {
+        let arena = Arena::new(64);
+        let outer = arena.mark();
+        arena.get_next_mem_slice(Layout::new::<[u8; 8]>()).unwrap();
+        let inner = arena.mark();
+        arena.get_next_mem_slice(Layout::new::<[u8; 8]>()).unwrap();
+
+        unsafe {
+            assert!(arena.rollback_to(outer));
+            assert!(!arena.rollback_to(inner));
+
+            // allocating past the stale mark doesn't make it valid again
+            arena.get_next_mem_slice(Layout::new::<[u8; 12]>()).unwrap();
+            assert!(!arena.rollback_to(inner));
+            assert_eq!(arena.used_space(), 12);
+
+            // marks created after the rollback are valid
+            let after = arena.mark();
+            arena.get_next_mem_slice(Layout::new::<[u8; 4]>()).unwrap();
+            assert!(arena.rollback_to(after));
+            assert_eq!(arena.used_space(), 12);
+        }
+    }
+
+    #[test]
+    fn reset_invalidates_marks() {
+        let mut arena = Arena::new(64);
+        arena.get_next_mem_slice(Layout::new::<[u8; 8]>()).unwrap();
+        let mark = arena.mark();
+        arena.reset();
+        arena.get_next_mem_slice(Layout::new::<[u8; 16]>()).unwrap();
+
+        assert!(!unsafe { arena.rollback_to(mark) });
+    }
+
+    #[test]
+    fn reclaim_stops_at_mark() {
+        let arena_alloc = ArenaAllocator::new(Arena::new(64));
+        let arena = unsafe { arena_alloc.get_arena() };
+
+        let vec1 = Vec::<u8, ArenaAllocator>::with_capacity_in(8, arena_alloc.clone());
+        let mark = arena.mark();
+        let vec2 = Vec::<u8, ArenaAllocator>::with_capacity_in(8, arena_alloc.clone());
+        drop(vec1);
+        drop(vec2);
+
+        assert_eq!(arena.live_allocations(), 0);
+        assert_eq!(arena.used_space(), 8);
+        assert!(unsafe { arena.rollback_to(mark) });
+    }
+
+    #[test]
+    fn scopes_rewind_on_exit() {
+        let mut arena = Arena::new_aligned(256, 8);
+        arena.get_next_mem_slice(Layout::new::<u32>()).unwrap();
+
+        let sum = arena.scope(|arena| {
+            let mut values = Vec::new_in(arena);
+            values.extend(0u64..8);
+            let nested = arena.alloc_droppable(Vec::from([1u8]));
+            nested.push(2);
+            values.iter().sum::<u64>() + nested.len() as u64
+        });
+        assert_eq!(sum, 30);
+        assert_eq!(arena.used_space(), 4);
+
+        // the arena is rewound when the closure panics as well
+        let unwound = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+            arena.scope(|arena| {
+                arena.alloc([0u8; 32]);
+                panic!("failed inside the scope");
+            })
+        }));
+        assert!(unwound.is_err());
+        assert_eq!(arena.used_space(), 4);
+    }
+
+    #[test]
+    fn reset_inside_checkpoint() {
+        let mut arena = Arena::new(64);
+        arena.get_next_mem_slice(Layout::new::<u32>()).unwrap();
+
+        let mut checkpoint = arena.checkpoint();
+        checkpoint.reset();
+        // allocations past the old position of the checkpoint aren't rolled back either
+        checkpoint.get_next_mem_slice(Layout::new::<[u8; 8]>()).unwrap();
+        drop(checkpoint);
+
+        assert_eq!(arena.used_space(), 8);
+    }
+}
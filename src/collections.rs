@@ -0,0 +1,250 @@
+//! collections whose buffers live in an arena, so everyday code doesn't have to spell out the allocator.
+
+use alloc::collections::TryReserveError;
+use core::{
+    fmt,
+    ops::{Deref, DerefMut},
+    str,
+};
+
+use crate::ArenaAllocator;
+
+/// `Vec` that allocates from an arena. the allocator is borrowed, so no reference count is touched per collection.
+pub type ArenaVec<'a, T> = alloc::vec::Vec<T, &'a ArenaAllocator>;
+
+/// growable utf-8 string whose buffer lives in an arena, the `String` counterpart of `ArenaVec`.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct ArenaString<'a> {
+    bytes: ArenaVec<'a, u8>,
+}
+
+impl<'a> ArenaString<'a> {
+    pub fn new_in(alloc: &'a ArenaAllocator) -> Self {
+        Self {
+            bytes: ArenaVec::new_in(alloc),
+        }
+    }
+
+    pub fn with_capacity_in(capacity: usize, alloc: &'a ArenaAllocator) -> Self {
+        Self {
+            bytes: ArenaVec::with_capacity_in(capacity, alloc),
+        }
+    }
+
+    /// copies `string` into a new string in the arena.
+    pub fn from_str_in(string: &str, alloc: &'a ArenaAllocator) -> Self {
+        let mut arena_string = Self::with_capacity_in(string.len(), alloc);
+        arena_string.push_str(string);
+        arena_string
+    }
+
+    /// same as `from_str_in` but returns an error instead of panicking if the arena is exhausted.
+    pub fn try_from_str_in(string: &str, alloc: &'a ArenaAllocator) -> Result<Self, TryReserveError> {
+        let mut bytes = ArenaVec::new_in(alloc);
+        bytes.try_reserve_exact(string.len())?;
+        bytes.extend_from_slice(string.as_bytes());
+        Ok(Self { bytes })
+    }
+
+    pub fn push(&mut self, ch: char) {
+        self.push_str(ch.encode_utf8(&mut [0; 4]));
+    }
+
+    pub fn push_str(&mut self, string: &str) {
+        self.bytes.extend_from_slice(string.as_bytes());
+    }
+
+    pub fn as_str(&self) -> &str {
+        unsafe { str::from_utf8_unchecked(&self.bytes) }
+    }
+
+    pub fn as_mut_str(&mut self) -> &mut str {
+        unsafe { str::from_utf8_unchecked_mut(&mut self.bytes) }
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.bytes.capacity()
+    }
+
+    pub fn clear(&mut self) {
+        self.bytes.clear();
+    }
+
+    /// returns the underlying bytes without copying them.
+    pub fn into_bytes(self) -> ArenaVec<'a, u8> {
+        self.bytes
+    }
+}
+
+impl Deref for ArenaString<'_> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl DerefMut for ArenaString<'_> {
+    fn deref_mut(&mut self) -> &mut str {
+        self.as_mut_str()
+    }
+}
+
+impl AsRef<str> for ArenaString<'_> {
+    fn as_ref(&self) -> &str {
+        self
+    }
+}
+
+impl PartialEq<str> for ArenaString<'_> {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for ArenaString<'_> {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl fmt::Write for ArenaString<'_> {
+    fn write_str(&mut self, string: &str) -> fmt::Result {
+        self.push_str(string);
+        Ok(())
+    }
+}
+
+impl fmt::Display for ArenaString<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Debug for ArenaString<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl<'s> Extend<&'s str> for ArenaString<'_> {
+    fn extend<I: IntoIterator<Item = &'s str>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|string| self.push_str(string));
+    }
+}
+
+impl Extend<char> for ArenaString<'_> {
+    fn extend<I: IntoIterator<Item = char>>(&mut self, iter: I) {
+        iter.into_iter().for_each(|ch| self.push(ch));
+    }
+}
+
+impl ArenaAllocator {
+    /// returns an empty vector that allocates from the arena.
+    pub fn vec<T>(&self) -> ArenaVec<'_, T> {
+        ArenaVec::new_in(self)
+    }
+
+    /// returns an empty vector with space for `capacity` values in the arena.
+    ///
+    /// # Panics
+    /// calls `handle_alloc_error` if the arena is exhausted.
+    pub fn vec_with_capacity<T>(&self, capacity: usize) -> ArenaVec<'_, T> {
+        ArenaVec::with_capacity_in(capacity, self)
+    }
+
+    /// returns an empty string that allocates from the arena.
+    pub fn string(&self) -> ArenaString<'_> {
+        ArenaString::new_in(self)
+    }
+
+    /// returns an empty string with space for `capacity` bytes in the arena.
+    ///
+    /// # Panics
+    /// calls `handle_alloc_error` if the arena is exhausted.
+    pub fn string_with_capacity(&self, capacity: usize) -> ArenaString<'_> {
+        ArenaString::with_capacity_in(capacity, self)
+    }
+}
+
+/// creates a `Vec` in the passed allocator with the syntax of `vec!`, e.g. `avec![in &alloc; 1, 2, 3]` or
+/// `avec![in &alloc; 0; 16]`. works with every allocator, with `&ArenaAllocator` it creates an `ArenaVec`.
+#[macro_export]
+macro_rules! avec {
+    (in $alloc:expr) => {
+        $crate::__private::Vec::new_in($alloc)
+    };
+    (in $alloc:expr; $elem:expr; $n:expr) => {{
+        let mut vec = $crate::__private::Vec::with_capacity_in($n, $alloc);
+        vec.resize($n, $elem);
+        vec
+    }};
+    (in $alloc:expr; $($x:expr),+ $(,)?) => {{
+        let values = [$($x),+];
+        let mut vec = $crate::__private::Vec::with_capacity_in(values.len(), $alloc);
+        vec.extend(values);
+        vec
+    }};
+}
+
+/// types used by the macros of the crate.
+#[doc(hidden)]
+pub mod __private {
+    pub use alloc::vec::Vec;
+}
+
+/// the types needed for everyday use of the arena, `use xs_arena_allocator::prelude::*` to import them at once.
+pub mod prelude {
+    pub use super::{ArenaString, ArenaVec};
+    pub use crate::{avec, Arena, ArenaAllocator};
+    #[cfg(feature = "hashbrown")]
+    pub use crate::{ArenaHashMap, ArenaHashSet};
+}
+
+#[cfg(test)]
+mod test {
+    use core::fmt::Write;
+
+    use super::prelude::*;
+
+    #[test]
+    fn vectors() {
+        let alloc = ArenaAllocator::new(Arena::new_aligned(256, 8));
+        let mut values: ArenaVec<'_, u32> = alloc.vec_with_capacity(4);
+        values.extend([1, 2, 3]);
+        assert_eq!(values, [1, 2, 3]);
+        assert_eq!(alloc.stats().allocations, 1);
+
+        let filled = avec![in &alloc; 7u8; 4];
+        assert_eq!(filled, [7; 4]);
+        let listed = avec![in &alloc; 1u64, 2, 3,];
+        assert_eq!(listed, [1, 2, 3]);
+        let empty: ArenaVec<'_, u8> = avec![in &alloc];
+        assert!(empty.is_empty());
+
+        // the macro works with owned allocators as well
+        let owned = avec![in alloc.clone(); 'a', 'b'];
+        assert_eq!(owned, ['a', 'b']);
+        assert!(alloc.vec::<u8>().is_empty());
+    }
+
+    #[test]
+    fn strings() {
+        let alloc = ArenaAllocator::new(Arena::new(256));
+        let mut string = alloc.string();
+        string.push_str("arena");
+        string.push(' ');
+        write!(string, "{}", 42).unwrap();
+        assert_eq!(string, "arena 42");
+        assert_eq!(format!("{string:?}"), "\"arena 42\"");
+
+        string.make_ascii_uppercase();
+        assert_eq!(string.as_str(), "ARENA 42");
+        assert_eq!(ArenaString::from_str_in("ARENA 42", &alloc), string);
+
+        let mut other = alloc.string_with_capacity(8);
+        other.extend(['a', 'b']);
+        other.extend(["cd", "ef"]);
+        assert_eq!(other.into_bytes(), *b"abcdef");
+    }
+}
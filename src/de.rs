@@ -0,0 +1,357 @@
+#[cfg(feature = "hashbrown")]
+use core::hash::Hash;
+use core::{alloc::Layout, fmt, marker::PhantomData, mem, ptr, slice, str};
+
+#[cfg(feature = "hashbrown")]
+use serde::de::MapAccess;
+use serde::de::{self, DeserializeSeed, Deserializer, SeqAccess, Visitor};
+
+#[cfg(feature = "hashbrown")]
+use crate::ArenaHashMap;
+use crate::{ArenaAllocator, ArenaString, ArenaVec};
+
+/// preallocation of sequences is capped, so a bogus length hint can't exhaust the arena up front.
+const MAX_PREALLOCATED_BYTES: usize = 4096;
+
+/// types that can be deserialized with all of their buffers in an arena, see `ArenaSeed`.
+/// implemented for strings, byte slices, `ArenaString`, `ArenaVec`, `ArenaHashMap`, `Option` and the primitive
+/// types. aggregates implement it by deserializing their fields with `ArenaSeed`.
+pub trait DeserializeIn<'a>: Sized {
+    fn deserialize_in<'de, D: Deserializer<'de>>(alloc: &'a ArenaAllocator, deserializer: D) -> Result<Self, D::Error>;
+}
+
+/// `DeserializeSeed` that deserializes a `T` into the arena instead of the global heap, e.g. to parse the json
+/// body of a request without leaving garbage on the heap. running out of space in the arena is reported as an
+/// error of the deserializer.
+///
+/// `&str` and `&[u8]` are copied into the arena as typed allocations, so they are only reclaimed on reset.
+pub struct ArenaSeed<'a, T> {
+    alloc: &'a ArenaAllocator,
+    _value: PhantomData<fn() -> T>,
+}
+
+impl<'a, T> ArenaSeed<'a, T> {
+    pub fn new(alloc: &'a ArenaAllocator) -> Self {
+        Self {
+            alloc,
+            _value: PhantomData,
+        }
+    }
+}
+
+impl<T> Clone for ArenaSeed<'_, T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for ArenaSeed<'_, T> {}
+
+impl<'de, 'a, T: DeserializeIn<'a>> DeserializeSeed<'de> for ArenaSeed<'a, T> {
+    type Value = T;
+
+    fn deserialize<D: Deserializer<'de>>(self, deserializer: D) -> Result<T, D::Error> {
+        T::deserialize_in(self.alloc, deserializer)
+    }
+}
+
+/// copies `bytes` into the arena.
+fn copy_bytes<'a, E: de::Error>(alloc: &'a ArenaAllocator, bytes: &[u8]) -> Result<&'a [u8], E> {
+    let layout = Layout::array::<u8>(bytes.len()).map_err(E::custom)?;
+    let block = alloc.arena.try_allocate(layout).map_err(E::custom)?;
+    unsafe {
+        let start = block.as_mut_ptr();
+        ptr::copy_nonoverlapping(bytes.as_ptr(), start, bytes.len());
+        Ok(slice::from_raw_parts(start, bytes.len()))
+    }
+}
+
+/// returns how many values to reserve for a sequence with the length `hint`.
+fn preallocated<T>(hint: Option<usize>) -> usize {
+    hint.unwrap_or(0)
+        .min(MAX_PREALLOCATED_BYTES / mem::size_of::<T>().max(1))
+}
+
+struct StrVisitor<'a>(&'a ArenaAllocator);
+
+impl<'a> Visitor<'_> for StrVisitor<'a> {
+    type Value = &'a str;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a string")
+    }
+
+    fn visit_str<E: de::Error>(self, string: &str) -> Result<Self::Value, E> {
+        let bytes = copy_bytes(self.0, string.as_bytes())?;
+        Ok(unsafe { str::from_utf8_unchecked(bytes) })
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Self::Value, E> {
+        let string = str::from_utf8(bytes).map_err(|_| E::invalid_value(de::Unexpected::Bytes(bytes), &self))?;
+        self.visit_str(string)
+    }
+}
+
+impl<'a> DeserializeIn<'a> for &'a str {
+    fn deserialize_in<'de, D: Deserializer<'de>>(alloc: &'a ArenaAllocator, deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_str(StrVisitor(alloc))
+    }
+}
+
+struct BytesVisitor<'a>(&'a ArenaAllocator);
+
+impl<'a> Visitor<'_> for BytesVisitor<'a> {
+    type Value = &'a [u8];
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a byte array")
+    }
+
+    fn visit_bytes<E: de::Error>(self, bytes: &[u8]) -> Result<Self::Value, E> {
+        copy_bytes(self.0, bytes)
+    }
+
+    fn visit_str<E: de::Error>(self, string: &str) -> Result<Self::Value, E> {
+        self.visit_bytes(string.as_bytes())
+    }
+}
+
+impl<'a> DeserializeIn<'a> for &'a [u8] {
+    fn deserialize_in<'de, D: Deserializer<'de>>(alloc: &'a ArenaAllocator, deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_bytes(BytesVisitor(alloc))
+    }
+}
+
+struct StringVisitor<'a>(&'a ArenaAllocator);
+
+impl<'a> Visitor<'_> for StringVisitor<'a> {
+    type Value = ArenaString<'a>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a string")
+    }
+
+    fn visit_str<E: de::Error>(self, string: &str) -> Result<Self::Value, E> {
+        ArenaString::try_from_str_in(string, self.0).map_err(E::custom)
+    }
+}
+
+impl<'a> DeserializeIn<'a> for ArenaString<'a> {
+    fn deserialize_in<'de, D: Deserializer<'de>>(alloc: &'a ArenaAllocator, deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_string(StringVisitor(alloc))
+    }
+}
+
+struct VecVisitor<'a, T>(&'a ArenaAllocator, PhantomData<fn() -> T>);
+
+impl<'de, 'a, T: DeserializeIn<'a>> Visitor<'de> for VecVisitor<'a, T> {
+    type Value = ArenaVec<'a, T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a sequence")
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut values = ArenaVec::new_in(self.0);
+        values
+            .try_reserve_exact(preallocated::<T>(seq.size_hint()))
+            .map_err(de::Error::custom)?;
+        while let Some(value) = seq.next_element_seed(ArenaSeed::new(self.0))? {
+            values.try_reserve(1).map_err(de::Error::custom)?;
+            values.push(value);
+        }
+        Ok(values)
+    }
+}
+
+impl<'a, T: DeserializeIn<'a>> DeserializeIn<'a> for ArenaVec<'a, T> {
+    fn deserialize_in<'de, D: Deserializer<'de>>(alloc: &'a ArenaAllocator, deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_seq(VecVisitor(alloc, PhantomData))
+    }
+}
+
+struct OptionVisitor<'a, T>(&'a ArenaAllocator, PhantomData<fn() -> T>);
+
+impl<'de, 'a, T: DeserializeIn<'a>> Visitor<'de> for OptionVisitor<'a, T> {
+    type Value = Option<T>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("an option")
+    }
+
+    fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+        Ok(None)
+    }
+
+    fn visit_some<D: Deserializer<'de>>(self, deserializer: D) -> Result<Self::Value, D::Error> {
+        T::deserialize_in(self.0, deserializer).map(Some)
+    }
+}
+
+impl<'a, T: DeserializeIn<'a>> DeserializeIn<'a> for Option<T> {
+    fn deserialize_in<'de, D: Deserializer<'de>>(alloc: &'a ArenaAllocator, deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_option(OptionVisitor(alloc, PhantomData))
+    }
+}
+
+#[cfg(feature = "hashbrown")]
+struct MapVisitor<'a, K, V>(&'a ArenaAllocator, PhantomData<fn() -> (K, V)>);
+
+#[cfg(feature = "hashbrown")]
+impl<'de, 'a, K, V> Visitor<'de> for MapVisitor<'a, K, V>
+where
+    K: DeserializeIn<'a> + Eq + Hash,
+    V: DeserializeIn<'a>,
+{
+    type Value = ArenaHashMap<'a, K, V>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a map")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let exhausted = |_| de::Error::custom("the arena is exhausted");
+        let mut entries = ArenaHashMap::new_in(self.0);
+        entries
+            .try_reserve(preallocated::<(K, V)>(map.size_hint()))
+            .map_err(exhausted)?;
+        while let Some((key, value)) = map.next_entry_seed(ArenaSeed::new(self.0), ArenaSeed::new(self.0))? {
+            entries.try_reserve(1).map_err(exhausted)?;
+            entries.insert(key, value);
+        }
+        Ok(entries)
+    }
+}
+
+#[cfg(feature = "hashbrown")]
+impl<'a, K, V> DeserializeIn<'a> for ArenaHashMap<'a, K, V>
+where
+    K: DeserializeIn<'a> + Eq + Hash,
+    V: DeserializeIn<'a>,
+{
+    fn deserialize_in<'de, D: Deserializer<'de>>(alloc: &'a ArenaAllocator, deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_map(MapVisitor(alloc, PhantomData))
+    }
+}
+
+/// implements `DeserializeIn` for types that don't allocate.
+macro_rules! deserialize_in_place {
+    ($($ty:ty),+) => {$(
+        impl DeserializeIn<'_> for $ty {
+            fn deserialize_in<'de, D: Deserializer<'de>>(_: &ArenaAllocator, deserializer: D) -> Result<Self, D::Error> {
+                serde::Deserialize::deserialize(deserializer)
+            }
+        }
+    )+};
+}
+
+deserialize_in_place!(
+    bool,
+    char,
+    (),
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    f32,
+    f64
+);
+
+#[cfg(test)]
+mod test {
+    use serde::de::{MapAccess, Visitor};
+
+    use super::*;
+    use crate::Arena;
+
+    /// request body whose strings and tags all end up in the arena.
+    #[derive(Debug)]
+    struct Request<'a> {
+        user: &'a str,
+        tags: ArenaVec<'a, ArenaString<'a>>,
+        limit: Option<u32>,
+    }
+
+    struct RequestVisitor<'a>(&'a ArenaAllocator);
+
+    impl<'de, 'a> Visitor<'de> for RequestVisitor<'a> {
+        type Value = Request<'a>;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a request")
+        }
+
+        fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+            let (mut user, mut tags, mut limit) = (None, None, None);
+            while let Some(key) = map.next_key_seed(ArenaSeed::<&str>::new(self.0))? {
+                match key {
+                    "user" => user = Some(map.next_value_seed(ArenaSeed::new(self.0))?),
+                    "tags" => tags = Some(map.next_value_seed(ArenaSeed::new(self.0))?),
+                    "limit" => limit = map.next_value_seed(ArenaSeed::new(self.0))?,
+                    _ => return Err(de::Error::unknown_field(key, &["user", "tags", "limit"])),
+                }
+            }
+            Ok(Request {
+                user: user.ok_or_else(|| de::Error::missing_field("user"))?,
+                tags: tags.unwrap_or_else(|| ArenaVec::new_in(self.0)),
+                limit,
+            })
+        }
+    }
+
+    impl<'a> DeserializeIn<'a> for Request<'a> {
+        fn deserialize_in<'de, D: Deserializer<'de>>(
+            alloc: &'a ArenaAllocator,
+            deserializer: D,
+        ) -> Result<Self, D::Error> {
+            deserializer.deserialize_map(RequestVisitor(alloc))
+        }
+    }
+
+    #[test]
+    fn deserializes_into_the_arena() {
+        let alloc = ArenaAllocator::new(Arena::new_aligned(1024, 8));
+        let json = r#"{"user": "ada", "tags": ["admin", "ops"], "limit": 10}"#;
+        let request = ArenaSeed::<Request>::new(&alloc)
+            .deserialize(&mut serde_json::Deserializer::from_str(json))
+            .unwrap();
+        assert_eq!(request.user, "ada");
+        assert_eq!(request.tags, ["admin", "ops"]);
+        assert_eq!(request.limit, Some(10));
+        // the keys, the user, the vector and both tags
+        assert_eq!(alloc.stats().allocations, 3 + 1 + 1 + 2);
+
+        let exhausted = ArenaAllocator::new(Arena::new(16));
+        let error = ArenaSeed::<ArenaVec<&str>>::new(&exhausted)
+            .deserialize(&mut serde_json::Deserializer::from_str(
+                r#"["a long string that doesn't fit"]"#,
+            ))
+            .unwrap_err();
+        assert!(error.to_string().contains("out of space"), "{error}");
+    }
+
+    #[cfg(feature = "hashbrown")]
+    #[test]
+    fn deserializes_maps() {
+        let alloc = ArenaAllocator::new(Arena::new_aligned(4096, 16));
+        let json = r#"{"a": [1, 2], "b": [], "c": [3]}"#;
+        let map = ArenaSeed::<ArenaHashMap<&str, ArenaVec<u8>>>::new(&alloc)
+            .deserialize(&mut serde_json::Deserializer::from_str(json))
+            .unwrap();
+        assert_eq!(map.len(), 3);
+        assert_eq!(map["a"], [1, 2]);
+        assert!(map["b"].is_empty());
+    }
+}
@@ -0,0 +1,193 @@
+use alloc::sync::Arc;
+use core::ptr::NonNull;
+
+use metrics::{counter, gauge, Counter, Gauge, SharedString};
+
+use crate::{Arena, ArenaAllocator};
+
+/// handles of the metrics of one arena, labeled with `arena` set to its name, e.g. to show arena exhaustion on a
+/// dashboard before it turns into failed requests. the handles are registered with the recorder that is installed
+/// when they are created, so the recorder has to be installed first.
+///
+/// counters: `xs_arena_allocations_total`, `xs_arena_allocated_bytes_total`, `xs_arena_failed_allocations_total`
+/// and `xs_arena_resets_total`.
+/// gauges: `xs_arena_used_bytes`, `xs_arena_capacity_bytes` and `xs_arena_utilization_percent`.
+pub struct ArenaMetrics {
+    allocations: Counter,
+    allocated_bytes: Counter,
+    failed_allocations: Counter,
+    resets: Counter,
+    used_bytes: Gauge,
+    capacity_bytes: Gauge,
+    utilization: Gauge,
+}
+
+impl ArenaMetrics {
+    pub fn new(name: impl Into<SharedString>) -> Self {
+        let name = name.into();
+        Self {
+            allocations: counter!("xs_arena_allocations_total", "arena" => name.clone()),
+            allocated_bytes: counter!("xs_arena_allocated_bytes_total", "arena" => name.clone()),
+            failed_allocations: counter!("xs_arena_failed_allocations_total", "arena" => name.clone()),
+            resets: counter!("xs_arena_resets_total", "arena" => name.clone()),
+            used_bytes: gauge!("xs_arena_used_bytes", "arena" => name.clone()),
+            capacity_bytes: gauge!("xs_arena_capacity_bytes", "arena" => name.clone()),
+            utilization: gauge!("xs_arena_utilization_percent", "arena" => name),
+        }
+    }
+
+    /// sets the gauges to the current state of `arena`. allocators with metrics do this on every allocation,
+    /// arenas that are used directly have to be published periodically.
+    pub fn publish(&self, arena: &Arena) {
+        let (used, capacity) = (arena.used_space(), arena.capacity());
+        self.used_bytes.set(used as f64);
+        self.capacity_bytes.set(capacity as f64);
+        self.utilization.set(if capacity == 0 {
+            100.0
+        } else {
+            used as f64 * 100.0 / capacity as f64
+        });
+    }
+
+    pub(crate) fn record_alloc(&self, arena: &Arena, block: Option<NonNull<[u8]>>) {
+        match block {
+            Some(block) => {
+                self.allocations.increment(1);
+                self.allocated_bytes.increment(block.len() as u64);
+            }
+            None => self.failed_allocations.increment(1),
+        }
+        self.publish(arena);
+    }
+
+    pub(crate) fn record_reset(&self, arena: &Arena) {
+        self.resets.increment(1);
+        self.publish(arena);
+    }
+}
+
+impl ArenaAllocator {
+    /// publishes the metrics of the arena labeled with `name` on every allocation, failed allocation and reset made
+    /// through this allocator and its clones, see `ArenaMetrics`. allocators that were cloned before don't publish.
+    pub fn with_metrics(mut self, name: impl Into<SharedString>) -> Self {
+        let metrics = ArenaMetrics::new(name);
+        metrics.publish(&self.arena);
+        self.metrics = Some(Arc::new(metrics));
+        self
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::alloc::{Allocator, Layout};
+    use std::{
+        collections::HashMap,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Mutex,
+        },
+    };
+
+    use metrics::{CounterFn, GaugeFn, Key, KeyName, Metadata, Recorder, Unit};
+
+    use super::*;
+
+    /// counter or gauge of `TestRecorder`, gauges store the bits of their `f64` value.
+    #[derive(Default)]
+    struct Value(AtomicU64);
+
+    impl CounterFn for Value {
+        fn increment(&self, value: u64) {
+            self.0.fetch_add(value, Ordering::Relaxed);
+        }
+
+        fn absolute(&self, value: u64) {
+            self.0.store(value, Ordering::Relaxed);
+        }
+    }
+
+    impl Value {
+        fn update_gauge(&self, f: impl Fn(f64) -> f64) {
+            let _ = self
+                .0
+                .fetch_update(Ordering::Relaxed, Ordering::Relaxed, |bits| Some(f(f64::from_bits(bits)).to_bits()));
+        }
+    }
+
+    impl GaugeFn for Value {
+        fn increment(&self, value: f64) {
+            self.update_gauge(|gauge| gauge + value);
+        }
+
+        fn decrement(&self, value: f64) {
+            self.update_gauge(|gauge| gauge - value);
+        }
+
+        fn set(&self, value: f64) {
+            self.0.store(value.to_bits(), Ordering::Relaxed);
+        }
+    }
+
+    #[derive(Default)]
+    struct TestRecorder {
+        values: Mutex<HashMap<Key, Arc<Value>>>,
+    }
+
+    impl TestRecorder {
+        fn register(&self, key: &Key) -> Arc<Value> {
+            assert!(key
+                .labels()
+                .any(|label| label.key() == "arena" && label.value() == "requests"));
+            self.values.lock().unwrap().entry(key.clone()).or_default().clone()
+        }
+
+        fn value(&self, name: &'static str) -> u64 {
+            let key = Key::from_parts(name, vec![metrics::Label::new("arena", "requests")]);
+            self.values.lock().unwrap()[&key].0.load(Ordering::Relaxed)
+        }
+
+        fn gauge(&self, name: &'static str) -> f64 {
+            f64::from_bits(self.value(name))
+        }
+    }
+
+    impl Recorder for TestRecorder {
+        fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+        fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+        fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+            Counter::from_arc(self.register(key))
+        }
+
+        fn register_gauge(&self, key: &Key, _: &Metadata<'_>) -> Gauge {
+            Gauge::from_arc(self.register(key))
+        }
+
+        fn register_histogram(&self, _: &Key, _: &Metadata<'_>) -> metrics::Histogram {
+            metrics::Histogram::noop()
+        }
+    }
+
+    #[test]
+    fn allocator_publishes_metrics() {
+        let recorder = TestRecorder::default();
+        let mut alloc = metrics::with_local_recorder(&recorder, || {
+            ArenaAllocator::new(Arena::new_aligned(64, 8)).with_metrics("requests")
+        });
+        assert_eq!(recorder.gauge("xs_arena_capacity_bytes"), 64.0);
+
+        let block = alloc.allocate(Layout::new::<[u64; 2]>()).unwrap();
+        assert!(alloc.allocate(Layout::new::<[u8; 64]>()).is_err());
+        assert_eq!(recorder.value("xs_arena_allocations_total"), 1);
+        assert_eq!(recorder.value("xs_arena_allocated_bytes_total"), 16);
+        assert_eq!(recorder.value("xs_arena_failed_allocations_total"), 1);
+        assert_eq!(recorder.gauge("xs_arena_used_bytes"), 16.0);
+        assert_eq!(recorder.gauge("xs_arena_utilization_percent"), 25.0);
+
+        unsafe { alloc.deallocate(block.as_non_null_ptr(), Layout::new::<[u64; 2]>()) };
+        assert!(alloc.try_reset());
+        assert_eq!(recorder.value("xs_arena_resets_total"), 1);
+        assert_eq!(recorder.gauge("xs_arena_utilization_percent"), 0.0);
+    }
+}
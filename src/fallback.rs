@@ -0,0 +1,163 @@
+use core::{
+    alloc::{AllocError, Allocator, Layout},
+    ptr::{self, NonNull},
+};
+
+use crate::ArenaAllocator;
+
+/// allocator that serves allocations from an arena and falls back to a secondary allocator once the arena is full.
+/// deallocations are routed to whichever allocator owns the pointer, so the arena can be used as a fast path
+/// without failing hard on exhaustion.
+#[derive(Clone)]
+pub struct FallbackAllocator<F: Allocator> {
+    primary: ArenaAllocator,
+    fallback: F,
+}
+
+impl<F: Allocator> FallbackAllocator<F> {
+    pub fn new(primary: ArenaAllocator, fallback: F) -> Self {
+        Self { primary, fallback }
+    }
+
+    /// returns the allocator that is tried first.
+    pub fn primary(&self) -> &ArenaAllocator {
+        &self.primary
+    }
+
+    /// returns the allocator that is used once the arena is full.
+    pub fn fallback(&self) -> &F {
+        &self.fallback
+    }
+
+    /// returns whether the block of `ptr` was allocated from the arena.
+    fn owned_by_primary(&self, ptr: NonNull<u8>, layout: Layout) -> bool {
+        let start = self.primary.arena.mem_pool.as_mut_ptr().addr();
+        let end = start + self.primary.arena.capacity();
+        let addr = ptr.as_ptr().addr();
+
+        // zero sized allocations can be placed right at the end of the memory pool.
+        (start..end).contains(&addr) || (layout.size() == 0 && addr == end)
+    }
+
+    /// moves the block of `ptr` from the arena to the fallback allocator.
+    unsafe fn move_to_fallback(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+        zeroed: bool,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        let new_block = if zeroed {
+            self.fallback.allocate_zeroed(new_layout)?
+        } else {
+            self.fallback.allocate(new_layout)?
+        };
+
+        unsafe {
+            let size = old_layout.size().min(new_layout.size());
+            ptr::copy_nonoverlapping(ptr.as_ptr(), new_block.as_mut_ptr(), size);
+            self.primary.deallocate(ptr, old_layout);
+        }
+        Ok(new_block)
+    }
+}
+
+impl ArenaAllocator {
+    /// falls back to `fallback` once the arena is full instead of failing.
+    pub fn with_fallback<F: Allocator>(self, fallback: F) -> FallbackAllocator<F> {
+        FallbackAllocator::new(self, fallback)
+    }
+}
+
+unsafe impl<F: Allocator> Allocator for FallbackAllocator<F> {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.primary.allocate(layout).or_else(|_| self.fallback.allocate(layout))
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        unsafe {
+            if self.owned_by_primary(ptr, layout) {
+                self.primary.deallocate(ptr, layout);
+            } else {
+                self.fallback.deallocate(ptr, layout);
+            }
+        }
+    }
+
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        unsafe {
+            if !self.owned_by_primary(ptr, old_layout) {
+                return self.fallback.grow(ptr, old_layout, new_layout);
+            }
+
+            self.primary
+                .grow(ptr, old_layout, new_layout)
+                .or_else(|_| self.move_to_fallback(ptr, old_layout, new_layout, false))
+        }
+    }
+
+    unsafe fn grow_zeroed(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        unsafe {
+            if !self.owned_by_primary(ptr, old_layout) {
+                return self.fallback.grow_zeroed(ptr, old_layout, new_layout);
+            }
+
+            self.primary
+                .grow_zeroed(ptr, old_layout, new_layout)
+                .or_else(|_| self.move_to_fallback(ptr, old_layout, new_layout, true))
+        }
+    }
+
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        unsafe {
+            if !self.owned_by_primary(ptr, old_layout) {
+                return self.fallback.shrink(ptr, old_layout, new_layout);
+            }
+
+            self.primary
+                .shrink(ptr, old_layout, new_layout)
+                .or_else(|_| self.move_to_fallback(ptr, old_layout, new_layout, false))
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::{alloc::Global, boxed::Box, vec::Vec};
+
+    use super::*;
+    use crate::Arena;
+
+    #[test]
+    fn falls_back_when_full() {
+        let alloc = ArenaAllocator::new(Arena::new(64)).with_fallback(Global);
+
+        let small = Box::new_in(1u64, alloc.clone());
+        assert!(alloc.owned_by_primary(NonNull::from(&*small).cast(), Layout::new::<u64>()));
+
+        // grows out of the arena once it doesn't fit anymore
+        let mut vec = Vec::<u32, _>::new_in(alloc.clone());
+        vec.extend(0..100);
+        assert!(!alloc.owned_by_primary(NonNull::new(vec.as_mut_ptr()).unwrap().cast(), Layout::new::<u32>()));
+        assert!(vec.iter().copied().eq(0..100));
+
+        drop(vec);
+        drop(small);
+        assert_eq!(alloc.primary().arena.live_allocations(), 0);
+    }
+}
@@ -0,0 +1,140 @@
+//! `extern "C"` interface, so C and C++ code can allocate from the same arena as the rust side.
+//! the declarations are in `include/xs_arena.h`, which the build script regenerates with cbindgen.
+//! build with `cargo rustc --release --features ffi --crate-type cdylib` to get a shared library.
+
+use alloc::boxed::Box;
+use core::{alloc::Layout, ffi::c_void, ptr};
+
+use crate::{Arena, ArenaAllocator};
+
+/// alignment used when C passes an alignment of 0, the alignment of `max_align_t` on common platforms.
+const DEFAULT_ALIGN: usize = 16;
+
+/// opaque handle to an arena that is shared with C.
+pub struct XsArena {
+    allocator: ArenaAllocator,
+}
+
+impl XsArena {
+    /// returns the allocator of the arena behind the handle, e.g. to allocate from it on the rust side.
+    pub fn allocator(&self) -> &ArenaAllocator {
+        &self.allocator
+    }
+}
+
+impl ArenaAllocator {
+    /// turns the allocator into a handle that can be passed to C. the handle has to be released with
+    /// `xs_arena_destroy`. clones of the allocator on the rust side keep working and share the arena.
+    pub fn into_raw_handle(self) -> *mut XsArena {
+        Box::into_raw(Box::new(XsArena { allocator: self }))
+    }
+}
+
+fn layout(size: usize, align: usize) -> Option<Layout> {
+    Layout::from_size_align(size, if align == 0 { DEFAULT_ALIGN } else { align }).ok()
+}
+
+/// creates an arena with the passed capacity in bytes. returns null if the memory pool can't be allocated.
+#[no_mangle]
+pub extern "C" fn xs_arena_create(capacity: usize) -> *mut XsArena {
+    match Arena::try_new(capacity) {
+        Ok(arena) => ArenaAllocator::new(arena).into_raw_handle(),
+        Err(_) => ptr::null_mut(),
+    }
+}
+
+/// allocates `size` bytes aligned to `align`, which has to be a power of two or 0 for the default alignment of 16.
+/// returns null if the arena is full or the alignment is invalid.
+///
+/// # Safety
+/// `arena` must be a live handle returned by `xs_arena_create` or `ArenaAllocator::into_raw_handle`.
+#[no_mangle]
+pub unsafe extern "C" fn xs_arena_alloc(arena: *const XsArena, size: usize, align: usize) -> *mut c_void {
+    let arena = unsafe { &*arena };
+    layout(size, align)
+        .and_then(|layout| arena.allocator.arena.get_next_mem_slice(layout).ok())
+        .map_or(ptr::null_mut(), |block| block.as_mut_ptr().cast())
+}
+
+/// hands a block back to the arena. `size` and `align` have to be the ones passed to `xs_arena_alloc`.
+/// freeing null does nothing.
+///
+/// # Safety
+/// `arena` must be a live handle and `ptr` must have been allocated from it and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn xs_arena_free(arena: *const XsArena, ptr: *mut c_void, size: usize, align: usize) {
+    let arena = unsafe { &*arena };
+    if let (Some(ptr), Some(layout)) = (ptr::NonNull::new(ptr.cast()), layout(size, align)) {
+        unsafe { arena.allocator.arena.free_last(ptr, layout) };
+    }
+}
+
+/// resets the arena so its memory can be reused. returns false and leaves the arena untouched if blocks are
+/// still allocated or the rust side still holds a clone of the allocator.
+///
+/// # Safety
+/// `arena` must be a live handle that is not used by another thread at the same time.
+#[no_mangle]
+pub unsafe extern "C" fn xs_arena_reset(arena: *mut XsArena) -> bool {
+    unsafe { &mut *arena }.allocator.try_reset()
+}
+
+/// returns the amount of bytes that are currently used.
+///
+/// # Safety
+/// `arena` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn xs_arena_used(arena: *const XsArena) -> usize {
+    unsafe { &*arena }.allocator.arena.used_space()
+}
+
+/// returns the capacity of the arena in bytes.
+///
+/// # Safety
+/// `arena` must be a live handle.
+#[no_mangle]
+pub unsafe extern "C" fn xs_arena_capacity(arena: *const XsArena) -> usize {
+    unsafe { &*arena }.allocator.arena.capacity()
+}
+
+/// releases the handle. the arena is freed once the rust side dropped its clones of the allocator as well.
+/// destroying null does nothing.
+///
+/// # Safety
+/// `arena` must be null or a live handle, which must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn xs_arena_destroy(arena: *mut XsArena) {
+    if !arena.is_null() {
+        drop(unsafe { Box::from_raw(arena) });
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn c_api_round_trip() {
+        unsafe {
+            let arena = xs_arena_create(128);
+            assert!(!arena.is_null());
+            assert_eq!(xs_arena_capacity(arena), 128);
+
+            let block = xs_arena_alloc(arena, 24, 0);
+            assert_eq!(block.align_offset(DEFAULT_ALIGN), 0);
+            assert!(xs_arena_alloc(arena, 8, 3).is_null());
+            assert!(xs_arena_alloc(arena, 1024, 8).is_null());
+
+            // the rust side shares the arena
+            let allocator = (*arena).allocator().clone();
+            assert_eq!(allocator.arena.used_space(), xs_arena_used(arena));
+            assert!(!xs_arena_reset(arena));
+            drop(allocator);
+
+            xs_arena_free(arena, block, 24, 0);
+            assert!(xs_arena_reset(arena));
+            assert_eq!(xs_arena_used(arena), 0);
+            xs_arena_destroy(arena);
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use alloc::vec::Vec;
+
+use crate::{Arena, Handle};
+
+/// per-frame allocator of a game loop, made of a ring of arenas that are used one frame at a time.
+/// `begin_frame` recycles the arena that was filled the longest time ago, so with two buffers the values of the
+/// previous frame are still there while the current frame is built, e.g. to interpolate between the two.
+///
+/// references into the current arena end with the frame since starting a frame takes `&mut self`, values that
+/// have to outlive it are inserted with `insert`. their handles resolve until the buffer is recycled and return
+/// `None` afterwards.
+pub struct FrameArena {
+    arenas: Vec<Arena>,
+    current: usize,
+    frame: u64,
+}
+
+impl FrameArena {
+    /// creates a double-buffered frame arena with `capacity` bytes per frame.
+    pub fn new(capacity: usize) -> Self {
+        Self::with_buffers(2, capacity)
+    }
+
+    /// creates a frame arena that keeps the values of the last `buffers - 1` frames alive.
+    ///
+    /// # Panics
+    /// panics if `buffers` is 0.
+    pub fn with_buffers(buffers: usize, capacity: usize) -> Self {
+        assert!(buffers > 0, "a frame arena needs at least one buffer");
+        Self {
+            arenas: (0..buffers).map(|_| Arena::new(capacity)).collect(),
+            current: 0,
+            frame: 0,
+        }
+    }
+
+    /// recycles the arena of the frame that is starting and returns it. values that were allocated in it
+    /// `buffers` frames ago are dropped and their handles stop resolving.
+    pub fn begin_frame(&mut self) -> &Arena {
+        let arena = &mut self.arenas[self.current];
+        arena.reset();
+        arena
+    }
+
+    /// finishes the current frame, its values stay alive until its arena is recycled.
+    pub fn end_frame(&mut self) {
+        self.current = (self.current + 1) % self.arenas.len();
+        self.frame += 1;
+    }
+
+    /// returns the arena of the current frame.
+    pub fn current(&self) -> &Arena {
+        &self.arenas[self.current]
+    }
+
+    /// returns the arena of the frame before the current one, which is the current one with a single buffer.
+    pub fn previous(&self) -> &Arena {
+        &self.arenas[(self.current + self.arenas.len() - 1) % self.arenas.len()]
+    }
+
+    /// returns the amount of frames that were ended.
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    /// moves `value` into the arena of the current frame, see `Arena::insert`.
+    ///
+    /// # Panics
+    /// calls `handle_alloc_error` if the arena is exhausted.
+    #[cfg_attr(feature = "profile", track_caller)]
+    pub fn insert<T: Send + Sync>(&self, value: T) -> Handle<T> {
+        self.current().insert(value)
+    }
+
+    /// returns the value of `handle`, or `None` if the arena of its frame was recycled.
+    pub fn get<T>(&self, handle: Handle<T>) -> Option<&T> {
+        self.arenas.iter().find_map(|arena| arena.get(handle))
+    }
+
+    /// same as `get` but returns a mutable reference.
+    pub fn get_mut<T>(&mut self, handle: Handle<T>) -> Option<&mut T> {
+        self.arenas.iter_mut().find_map(|arena| arena.get_mut(handle))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::{string::String, vec};
+
+    use super::*;
+
+    #[test]
+    fn values_live_through_the_next_frame() {
+        let mut frames = FrameArena::new(1024);
+        frames.begin_frame();
+        let first = frames.insert(String::from("frame 0"));
+        frames.end_frame();
+
+        let arena = frames.begin_frame();
+        arena.alloc_slice_copy(&[0u8; 256]);
+        let second = frames.insert(vec![1, 2, 3]);
+        assert_eq!(frames.get(first).unwrap(), "frame 0");
+        frames.get_mut(second).unwrap().push(4);
+        assert!(frames.current().used_space() > 256);
+        assert!(frames.previous().used_space() < 256);
+        frames.end_frame();
+
+        // the buffer of the first frame is recycled, the second frame is still alive
+        frames.begin_frame();
+        assert!(frames.get(first).is_none());
+        assert_eq!(frames.get(second).unwrap(), &[1, 2, 3, 4]);
+        assert_eq!(frames.current().used_space(), 0);
+        frames.end_frame();
+        assert_eq!(frames.frame(), 3);
+    }
+
+    #[test]
+    fn ring_of_buffers() {
+        let mut frames = FrameArena::with_buffers(3, 64);
+        let mut handles = Vec::new();
+        for frame in 0..5u32 {
+            frames.begin_frame();
+            handles.push(frames.insert(frame));
+            frames.end_frame();
+        }
+
+        // every buffer holds one of the last frames until the next frame begins
+        let alive: Vec<_> = handles.iter().map(|&handle| frames.get(handle).copied()).collect();
+        assert_eq!(alive, [None, None, Some(2), Some(3), Some(4)]);
+        frames.begin_frame();
+        assert!(frames.get(handles[2]).is_none());
+    }
+}
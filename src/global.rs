@@ -0,0 +1,143 @@
+use core::{
+    alloc::{GlobalAlloc, Layout},
+    cell::UnsafeCell,
+    hint,
+    mem::MaybeUninit,
+    ptr::{self, NonNull},
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+use crate::Arena;
+
+const UNINIT: u8 = 0;
+const INITIALIZING: u8 = 1;
+const READY: u8 = 2;
+
+/// global allocator that serves every allocation from an arena on top of an `N` byte buffer stored inside itself.
+/// meant for `#[global_allocator]` in short-lived tools and firmware where all heap traffic should come from one
+/// preallocated region. memory is only reclaimed once every allocation has been freed.
+///
+/// the arena is created on the first allocation, so the buffer is placed wherever the static lives.
+/// the `track-allocations`, `track-threads` and `profile` features must be disabled, since they allocate while
+/// allocating.
+///
+/// ```no_run
+/// use xs_arena_allocator::StaticArenaAlloc;
+///
+/// #[global_allocator]
+/// static ALLOCATOR: StaticArenaAlloc<{ 1 << 20 }> = StaticArenaAlloc::new();
+///
+/// fn main() {
+///     let numbers: Vec<u32> = (0..100).collect();
+///     assert!(ALLOCATOR.arena().used_space() >= 400);
+/// }
+/// ```
+pub struct StaticArenaAlloc<const N: usize> {
+    buf: UnsafeCell<MaybeUninit<[u8; N]>>,
+    arena: UnsafeCell<MaybeUninit<Arena>>,
+    state: AtomicU8,
+}
+
+unsafe impl<const N: usize> Sync for StaticArenaAlloc<N> {}
+
+impl<const N: usize> StaticArenaAlloc<N> {
+    pub const fn new() -> Self {
+        Self {
+            buf: UnsafeCell::new(MaybeUninit::uninit()),
+            arena: UnsafeCell::new(MaybeUninit::uninit()),
+            state: AtomicU8::new(UNINIT),
+        }
+    }
+
+    /// returns the arena the allocations are served from, creating it if nothing was allocated yet.
+    pub fn arena(&self) -> &Arena {
+        if self.state.load(Ordering::Acquire) != READY {
+            self.init();
+        }
+        unsafe { (*self.arena.get()).assume_init_ref() }
+    }
+
+    #[cold]
+    fn init(&self) {
+        match self
+            .state
+            .compare_exchange(UNINIT, INITIALIZING, Ordering::Acquire, Ordering::Acquire)
+        {
+            Ok(_) => unsafe {
+                let mem = NonNull::slice_from_raw_parts(NonNull::new_unchecked(self.buf.get().cast::<u8>()), N);
+                (*self.arena.get()).write(Arena::from_external(mem));
+                self.state.store(READY, Ordering::Release);
+            },
+            Err(_) => {
+                while self.state.load(Ordering::Acquire) != READY {
+                    hint::spin_loop();
+                }
+            }
+        }
+    }
+}
+
+impl<const N: usize> Default for StaticArenaAlloc<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+unsafe impl<const N: usize> GlobalAlloc for StaticArenaAlloc<N> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        self.arena()
+            .get_next_mem_slice(layout)
+            .map_or(ptr::null_mut(), |slice| slice.as_mut_ptr())
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        unsafe { self.arena().free_last(NonNull::new_unchecked(ptr), layout) };
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        let new_layout = unsafe { Layout::from_size_align_unchecked(new_size, layout.align()) };
+        let arena = self.arena();
+        let ptr = unsafe { NonNull::new_unchecked(ptr) };
+
+        let in_place = arena
+            .grow_in_place(ptr, layout, new_layout)
+            .or_else(|| arena.resize_last(ptr, layout, new_layout));
+        if let Some(block) = in_place {
+            return block.as_mut_ptr();
+        }
+
+        let Ok(new_block) = arena.get_next_mem_slice(new_layout) else {
+            return ptr::null_mut();
+        };
+        unsafe { ptr::copy_nonoverlapping(ptr.as_ptr(), new_block.as_mut_ptr(), layout.size().min(new_size)) };
+        unsafe { arena.free_last(ptr, layout) };
+        new_block.as_mut_ptr()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    static ALLOCATOR: StaticArenaAlloc<256> = StaticArenaAlloc::new();
+
+    #[test]
+    fn static_allocator() {
+        unsafe {
+            let layout = Layout::new::<[u64; 4]>();
+            let first = ALLOCATOR.alloc(layout);
+            assert!(!first.is_null());
+            first.write_bytes(7, layout.size());
+
+            let grown = ALLOCATOR.realloc(first, layout, 64);
+            assert_eq!(grown, first);
+            assert_eq!(grown.add(31).read(), 7);
+
+            // the pool is exhausted instead of falling back to the system allocator
+            assert!(ALLOCATOR.alloc(Layout::new::<[u8; 256]>()).is_null());
+
+            ALLOCATOR.dealloc(grown, Layout::from_size_align(64, 8).unwrap());
+            assert_eq!(ALLOCATOR.arena().used_space(), 0);
+        }
+    }
+}
@@ -0,0 +1,269 @@
+use std::{
+    alloc::{AllocError, Allocator, Layout},
+    ptr::{self, NonNull},
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc, RwLock,
+    },
+};
+
+use crate::Arena;
+
+/// decides how large the chunks of a `GrowableArena` are.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GrowthPolicy {
+    /// every new chunk is twice as large as the previous one.
+    Doubling,
+    /// every new chunk has the passed capacity in bytes.
+    Fixed(usize),
+}
+
+/// arena that links an additional chunk of memory once the current one is exhausted instead of failing.
+/// chunks are only freed when the arena is dropped, so pointers that were handed out stay stable.
+/// allocations larger than the policy allows get a chunk of their own.
+pub struct GrowableArena {
+    chunks: RwLock<Vec<Arena>>,
+    /// index of the chunk that is allocated from, only advanced while the chunks are locked for writing.
+    current: AtomicUsize,
+    policy: GrowthPolicy,
+}
+
+impl GrowableArena {
+    /// create a new growable arena whose first chunk has the passed capacity in bytes.
+    pub fn new(initial_capacity: usize, policy: GrowthPolicy) -> Self {
+        Self {
+            chunks: RwLock::new(vec![Arena::new(initial_capacity)]),
+            current: AtomicUsize::new(0),
+            policy,
+        }
+    }
+
+    /// create a new growable arena that uses `first_chunk` until it is exhausted, e.g. an aligned or mapped arena.
+    pub fn from_arena(first_chunk: Arena, policy: GrowthPolicy) -> Self {
+        Self {
+            chunks: RwLock::new(vec![first_chunk]),
+            current: AtomicUsize::new(0),
+            policy,
+        }
+    }
+
+    /// returns the combined capacity of all chunks.
+    pub fn capacity(&self) -> usize {
+        self.chunks.read().unwrap().iter().map(Arena::capacity).sum()
+    }
+
+    /// returns the combined used space of all chunks.
+    pub fn used_space(&self) -> usize {
+        self.chunks.read().unwrap().iter().map(Arena::used_space).sum()
+    }
+
+    /// returns the amount of chunks that were allocated so far.
+    pub fn chunk_count(&self) -> usize {
+        self.chunks.read().unwrap().len()
+    }
+
+    /// resets every chunk so the memory can be reused. the chunks themselves are kept and are filled again in the
+    /// order they were allocated in.
+    pub fn reset(&mut self) {
+        self.chunks.get_mut().unwrap().iter_mut().for_each(Arena::reset);
+        *self.current.get_mut() = 0;
+    }
+
+    /// returns a pointer to a memory slice with the alignment of the passed `Layout`.
+    /// a new chunk is allocated if the current one is exhausted.
+    pub fn get_next_mem_slice(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        if let Ok(slice) = self.current_chunk_slice(&self.chunks.read().unwrap(), layout) {
+            return Ok(slice);
+        }
+
+        let mut chunks = self.chunks.write().unwrap();
+
+        // another thread might have added a chunk while we were waiting for the lock.
+        if let Ok(slice) = self.current_chunk_slice(&chunks, layout) {
+            return Ok(slice);
+        }
+
+        // chunks after the current one are only left over from before a reset.
+        let current = self.current.load(Ordering::Relaxed);
+        for (index, chunk) in chunks.iter().enumerate().skip(current + 1) {
+            if let Ok(slice) = chunk.get_next_mem_slice(layout) {
+                self.current.store(index, Ordering::Relaxed);
+                return Ok(slice);
+            }
+        }
+
+        let chunk = Arena::try_new(self.next_chunk_capacity(&chunks, layout)?)?;
+        let slice = chunk.get_next_mem_slice(layout)?;
+        chunks.push(chunk);
+        self.current.store(chunks.len() - 1, Ordering::Relaxed);
+        Ok(slice)
+    }
+
+    fn current_chunk_slice(&self, chunks: &[Arena], layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        let current = self.current.load(Ordering::Relaxed);
+        chunks.get(current).ok_or(AllocError)?.get_next_mem_slice(layout)
+    }
+
+    fn next_chunk_capacity(&self, chunks: &[Arena], layout: Layout) -> Result<usize, AllocError> {
+        let last_capacity = chunks.last().map_or(0, Arena::capacity);
+        let capacity = match self.policy {
+            GrowthPolicy::Doubling => last_capacity.saturating_mul(2),
+            GrowthPolicy::Fixed(capacity) => capacity,
+        };
+
+        // enough space for the allocation regardless of where the chunk is placed.
+        let required = layout.size().checked_add(layout.align() - 1).ok_or(AllocError)?;
+        Ok(capacity.max(required))
+    }
+
+    /// calls `f` with the chunk that contains the block of `ptr` with the passed layout.
+    fn with_owning_chunk<R>(&self, ptr: NonNull<u8>, layout: Layout, f: impl FnOnce(&Arena) -> R) -> Option<R> {
+        let chunks = self.chunks.read().unwrap();
+        chunks.iter().rev().find(|chunk| chunk.contains(ptr, layout)).map(f)
+    }
+}
+
+/// bump style allocator for a `GrowableArena`.
+#[derive(Clone)]
+pub struct GrowableArenaAllocator {
+    arena: Arc<GrowableArena>,
+}
+
+impl GrowableArenaAllocator {
+    pub fn new(arena: GrowableArena) -> Self {
+        Self { arena: Arc::new(arena) }
+    }
+
+    /// returns the arena this allocator allocates from.
+    pub fn arena(&self) -> &GrowableArena {
+        &self.arena
+    }
+}
+
+unsafe impl Allocator for GrowableArenaAllocator {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.arena.get_next_mem_slice(layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        self.arena.with_owning_chunk(ptr, layout, |chunk| unsafe { chunk.free_last(ptr, layout) });
+    }
+
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        let in_place = self
+            .arena
+            .with_owning_chunk(ptr, old_layout, |chunk| {
+                chunk
+                    .grow_in_place(ptr, old_layout, new_layout)
+                    .or_else(|| chunk.resize_last(ptr, old_layout, new_layout))
+            })
+            .flatten();
+        if let Some(block) = in_place {
+            return Ok(block);
+        }
+
+        let new_block = self.allocate(new_layout)?;
+        unsafe {
+            ptr::copy_nonoverlapping(ptr.as_ptr(), new_block.as_mut_ptr(), old_layout.size());
+            self.deallocate(ptr, old_layout);
+        }
+        Ok(new_block)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use super::*;
+
+    #[test]
+    fn doubling_growth() {
+        let arena = GrowableArena::new(64, GrowthPolicy::Doubling);
+        let slices: Vec<_> = (0..16u8)
+            .map(|i| {
+                let slice = arena.get_next_mem_slice(Layout::new::<[u8; 32]>()).unwrap();
+                unsafe { slice.as_mut_ptr().write_bytes(i, 32) };
+                slice
+            })
+            .collect();
+
+        // 64 + 128 + 256 + 512 bytes fit 2 + 4 + 8 + 16 slices
+        assert_eq!(arena.chunk_count(), 4);
+        assert_eq!(arena.capacity(), 960);
+        assert_eq!(arena.used_space(), 16 * 32);
+
+        for (i, slice) in slices.iter().enumerate() {
+            assert!(unsafe { slice.as_ref() }.iter().all(|&byte| byte == i as u8));
+        }
+    }
+
+    #[test]
+    fn fixed_growth() {
+        let arena = GrowableArena::new(64, GrowthPolicy::Fixed(128));
+        arena.get_next_mem_slice(Layout::new::<[u8; 64]>()).unwrap();
+        arena.get_next_mem_slice(Layout::new::<[u8; 64]>()).unwrap();
+        assert_eq!(arena.capacity(), 192);
+
+        // too large for the policy, so it gets a chunk of its own
+        arena.get_next_mem_slice(Layout::new::<[u8; 1000]>()).unwrap();
+        assert_eq!(arena.chunk_count(), 3);
+        assert_eq!(arena.capacity(), 1192);
+    }
+
+    #[test]
+    fn reset() {
+        let mut arena = GrowableArena::new(16, GrowthPolicy::Doubling);
+        for _ in 0..10 {
+            arena.get_next_mem_slice(Layout::new::<[u8; 8]>()).unwrap();
+        }
+
+        let capacity = arena.capacity();
+        let chunk_count = arena.chunk_count();
+        arena.reset();
+        assert_eq!(arena.used_space(), 0);
+        assert_eq!(arena.capacity(), capacity);
+
+        // the kept chunks are used again before a new one is allocated
+        for _ in 0..10 {
+            arena.get_next_mem_slice(Layout::new::<[u8; 8]>()).unwrap();
+        }
+        assert_eq!(arena.chunk_count(), chunk_count);
+        assert_eq!(arena.used_space(), 80);
+    }
+
+    #[test]
+    fn zero_sized_blocks_at_the_end_of_a_chunk() {
+        let alloc = GrowableArenaAllocator::new(GrowableArena::new(8, GrowthPolicy::Fixed(8)));
+        alloc.allocate(Layout::new::<[u8; 8]>()).unwrap();
+        let empty = alloc.allocate(Layout::new::<()>()).unwrap();
+        assert_eq!(alloc.arena().chunk_count(), 1);
+        assert_eq!(alloc.arena().chunks.read().unwrap()[0].live_allocations(), 2);
+
+        unsafe { alloc.deallocate(empty.as_non_null_ptr(), Layout::new::<()>()) };
+        assert_eq!(alloc.arena().chunks.read().unwrap()[0].live_allocations(), 1);
+    }
+
+    #[test]
+    fn allocator() {
+        let alloc = GrowableArenaAllocator::new(GrowableArena::new(32, GrowthPolicy::Doubling));
+        let join_handles: Vec<_> = (0..8)
+            .map(|i| {
+                let alloc = alloc.clone();
+                thread::spawn(move || {
+                    let mut vec = Vec::<u64, GrowableArenaAllocator>::new_in(alloc);
+                    vec.extend(0..100 * i);
+                    assert!(vec.iter().copied().eq(0..100 * i));
+                })
+            })
+            .collect();
+
+        join_handles.into_iter().for_each(|j| j.join().unwrap());
+        assert!(alloc.arena().chunk_count() > 1);
+    }
+}
@@ -0,0 +1,170 @@
+use core::{
+    fmt,
+    hash::{Hash, Hasher},
+    marker::PhantomData,
+    ptr::NonNull,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::Arena;
+
+/// source of the generations of all arenas, so a handle is never valid for an arena other than the one it came from.
+static NEXT_GENERATION: AtomicUsize = AtomicUsize::new(0);
+
+pub(crate) fn next_generation() -> usize {
+    NEXT_GENERATION.fetch_add(1, Ordering::Relaxed)
+}
+
+/// small copyable id of a value inserted with `Arena::insert`, e.g. to link the nodes of a graph without borrowing
+/// the arena. a handle only resolves in the arena that issued it and stops resolving once the arena is reset or
+/// rolled back below its value, so stale handles are caught instead of reading reused memory.
+pub struct Handle<T> {
+    offset: usize,
+    generation: usize,
+    /// rollback epoch of the arena when the value was inserted.
+    epoch: usize,
+    _value: PhantomData<fn() -> T>,
+}
+
+impl Arena {
+    /// moves `value` into the arena and returns a handle to it. the value is dropped when the arena is reset or
+    /// dropped, the same as values allocated with `alloc_droppable`.
+    /// handles can be copied to other threads that share the arena, so the value has to be `Sync`.
+    ///
+    /// ```compile_fail
+    /// use std::cell::Cell;
+    ///
+    /// let arena = xs_arena_allocator::Arena::new(64);
+    /// let counter = arena.insert(Cell::new(0u32));
+    /// ```
+    ///
+    /// # Panics
+    /// calls `handle_alloc_error` if the arena is exhausted.
+    #[cfg_attr(feature = "profile", track_caller)]
+    pub fn insert<T: Send + Sync>(&self, value: T) -> Handle<T> {
+        let epoch = self.rollbacks.lock().epoch();
+        let value = NonNull::from(self.alloc_droppable(value));
+        Handle {
+            offset: self.offset_of(value.cast()),
+            generation: self.generation.load(Ordering::Relaxed),
+            epoch,
+            _value: PhantomData,
+        }
+    }
+
+    /// returns the value of `handle`, or `None` if the arena was reset or rolled back below the value since it was
+    /// inserted.
+    pub fn get<T>(&self, handle: Handle<T>) -> Option<&T> {
+        self.resolve(handle).map(|value| unsafe { value.as_ref() })
+    }
+
+    /// same as `get` but returns a mutable reference.
+    pub fn get_mut<T>(&mut self, handle: Handle<T>) -> Option<&mut T> {
+        self.resolve(handle).map(|mut value| unsafe { value.as_mut() })
+    }
+
+    fn resolve<T>(&self, handle: Handle<T>) -> Option<NonNull<T>> {
+        if handle.generation != self.generation.load(Ordering::Relaxed)
+            || self
+                .rollbacks
+                .lock()
+                .furthest_since(handle.epoch)
+                .is_some_and(|target| target <= handle.offset)
+        {
+            return None;
+        }
+        Some(unsafe { self.base().add(handle.offset).cast() })
+    }
+}
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        (self.offset, self.generation, self.epoch) == (other.offset, other.generation, other.epoch)
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> Hash for Handle<T> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        (self.offset, self.generation, self.epoch).hash(state);
+    }
+}
+
+impl<T> fmt::Debug for Handle<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Handle")
+            .field("offset", &self.offset)
+            .field("generation", &self.generation)
+            .field("epoch", &self.epoch)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::vec::Vec;
+
+    use super::*;
+
+    struct Node {
+        value: u32,
+        edges: Vec<Handle<Node>>,
+    }
+
+    #[test]
+    fn graph_of_handles() {
+        let mut arena = Arena::new(1024);
+        let a = arena.insert(Node {
+            value: 1,
+            edges: Vec::new(),
+        });
+        let b = arena.insert(Node {
+            value: 2,
+            edges: Vec::from([a]),
+        });
+        arena.get_mut(a).unwrap().edges.push(b);
+
+        // cycles are followed through the handles without borrowing the nodes from each other
+        let next = arena.get(a).unwrap().edges[0];
+        assert_eq!(next, b);
+        assert_eq!(arena.get(arena.get(next).unwrap().edges[0]).unwrap().value, 1);
+
+        arena.reset();
+        assert!(arena.get(a).is_none());
+        let c = arena.insert(Node {
+            value: 3,
+            edges: Vec::new(),
+        });
+        assert_eq!(arena.get(c).unwrap().value, 3);
+    }
+
+    #[test]
+    fn handles_are_bound_to_their_arena() {
+        let mut arena = Arena::new(64);
+        let other = Arena::new(64);
+        let value = arena.insert(5u64);
+        assert!(other.get(value).is_none());
+
+        // rolling back only invalidates the handles of values above the mark
+        let inner = {
+            let checkpoint = arena.checkpoint();
+            checkpoint.insert(6u64)
+        };
+        assert!(arena.get(inner).is_none());
+        assert_eq!(arena.get(value), Some(&5));
+
+        let reused = arena.insert(7u64);
+        assert_eq!(reused.offset, inner.offset);
+        assert!(arena.get(inner).is_none());
+        assert_eq!(arena.get(reused), Some(&7));
+    }
+}
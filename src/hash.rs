@@ -0,0 +1,88 @@
+//! hash maps and sets whose tables live in an arena.
+
+use core::hash::Hash;
+
+use hashbrown::{DefaultHashBuilder, HashMap, HashSet};
+
+use crate::ArenaAllocator;
+
+/// `hashbrown::HashMap` that allocates its table from an arena.
+/// growing the map moves it to a new table, the old one is only reclaimed if nothing was allocated after the new
+/// one, so maps that are filled in one go should reserve their capacity up front.
+pub type ArenaHashMap<'a, K, V, S = DefaultHashBuilder> = HashMap<K, V, S, &'a ArenaAllocator>;
+
+/// `hashbrown::HashSet` that allocates its table from an arena, see `ArenaHashMap`.
+pub type ArenaHashSet<'a, T, S = DefaultHashBuilder> = HashSet<T, S, &'a ArenaAllocator>;
+
+impl ArenaAllocator {
+    /// returns an empty hash map that allocates from the arena.
+    pub fn hash_map<K, V>(&self) -> ArenaHashMap<'_, K, V> {
+        ArenaHashMap::new_in(self)
+    }
+
+    /// returns an empty hash map with space for `capacity` entries in the arena.
+    ///
+    /// # Panics
+    /// calls `handle_alloc_error` if the arena is exhausted.
+    pub fn hash_map_with_capacity<K, V>(&self, capacity: usize) -> ArenaHashMap<'_, K, V> {
+        ArenaHashMap::with_capacity_in(capacity, self)
+    }
+
+    /// returns an empty hash set that allocates from the arena.
+    pub fn hash_set<T: Eq + Hash>(&self) -> ArenaHashSet<'_, T> {
+        ArenaHashSet::new_in(self)
+    }
+
+    /// returns an empty hash set with space for `capacity` values in the arena.
+    ///
+    /// # Panics
+    /// calls `handle_alloc_error` if the arena is exhausted.
+    pub fn hash_set_with_capacity<T: Eq + Hash>(&self, capacity: usize) -> ArenaHashSet<'_, T> {
+        ArenaHashSet::with_capacity_in(capacity, self)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::Arena;
+
+    #[test]
+    fn maps_rehash_inside_the_arena() {
+        let mut alloc = ArenaAllocator::new(Arena::new_aligned(64 * 1024, 16));
+        {
+            let mut map = alloc.hash_map();
+            let mut resizes = 0;
+            let mut capacity = map.capacity();
+            for key in 0..1000u32 {
+                map.insert(key, key * 2);
+                if map.capacity() != capacity {
+                    capacity = map.capacity();
+                    resizes += 1;
+                }
+            }
+            assert!(resizes > 1);
+            assert_eq!(alloc.stats().allocations, resizes);
+            assert!((0..1000).all(|key| map[&key] == key * 2));
+
+            // the table of a map that reserved its capacity is never moved
+            let consumed = alloc.stats().consumed_bytes;
+            let mut reserved = alloc.hash_map_with_capacity(1000);
+            reserved.extend((0..1000u32).map(|key| (key, ())));
+            assert_eq!(alloc.stats().allocations, resizes + 1);
+            assert!(alloc.stats().consumed_bytes > consumed);
+        }
+        assert!(alloc.try_reset());
+    }
+
+    #[test]
+    fn sets_report_exhaustion() {
+        let alloc = ArenaAllocator::new(Arena::new_aligned(256, 16));
+        let mut set = alloc.hash_set();
+        set.extend([1u64, 2, 3]);
+        assert!(set.contains(&2));
+        assert!(set.try_reserve(1000).is_err());
+        assert_eq!(set.len(), 3);
+        assert!(alloc.hash_set_with_capacity::<u8>(0).is_empty());
+    }
+}
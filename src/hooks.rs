@@ -0,0 +1,113 @@
+use alloc::sync::Arc;
+use core::{
+    alloc::{AllocError, Layout},
+    ptr::NonNull,
+};
+
+use crate::ArenaAllocator;
+
+/// callbacks that an `ArenaAllocator` invokes, e.g. to instrument allocation hotspots or forward them to a tracer.
+/// every method does nothing by default, so only the interesting events have to be implemented.
+/// the callbacks run on the allocating thread and should be cheap, since they are on the hot path.
+pub trait ArenaHooks: Send + Sync {
+    /// called after `block` was handed out for `layout`.
+    fn on_alloc(&self, layout: Layout, block: NonNull<[u8]>) {
+        let _ = (layout, block);
+    }
+
+    /// called after an allocation for `layout` failed.
+    fn on_fail(&self, layout: Layout) {
+        let _ = layout;
+    }
+
+    /// called after the arena was reset with `ArenaAllocator::try_reset`.
+    fn on_reset(&self) {}
+}
+
+impl ArenaAllocator {
+    /// invokes `hooks` on every allocation, failed allocation and reset made through this allocator and its clones.
+    /// allocators that were cloned before don't invoke them.
+    pub fn with_hooks(mut self, hooks: impl ArenaHooks + 'static) -> Self {
+        self.hooks = Some(Arc::new(hooks));
+        self
+    }
+
+    /// reports the result of an allocation for `layout` to the hooks.
+    pub(crate) fn notify_alloc(
+        &self,
+        layout: Layout,
+        result: Result<NonNull<[u8]>, AllocError>,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        if let Some(hooks) = &self.hooks {
+            match result {
+                Ok(block) => hooks.on_alloc(layout, block),
+                Err(_) => hooks.on_fail(layout),
+            }
+        }
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.record_alloc(&self.arena, result.ok());
+        }
+        result
+    }
+
+    pub(crate) fn notify_reset(&self) {
+        if let Some(hooks) = &self.hooks {
+            hooks.on_reset();
+        }
+        #[cfg(feature = "metrics")]
+        if let Some(metrics) = &self.metrics {
+            metrics.record_reset(&self.arena);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::{boxed::Box, vec::Vec};
+    use core::{
+        alloc::Allocator,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    use super::*;
+    use crate::Arena;
+
+    #[derive(Default)]
+    struct Counters {
+        allocated_bytes: AtomicUsize,
+        failures: AtomicUsize,
+        resets: AtomicUsize,
+    }
+
+    impl ArenaHooks for Arc<Counters> {
+        fn on_alloc(&self, layout: Layout, _: NonNull<[u8]>) {
+            self.allocated_bytes.fetch_add(layout.size(), Ordering::Relaxed);
+        }
+
+        fn on_fail(&self, _: Layout) {
+            self.failures.fetch_add(1, Ordering::Relaxed);
+        }
+
+        fn on_reset(&self) {
+            self.resets.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn hooks_see_every_event() {
+        let counters = Arc::new(Counters::default());
+        let mut alloc = ArenaAllocator::new(Arena::new(64)).with_hooks(counters.clone());
+
+        let value = Box::new_in(1u64, alloc.clone());
+        assert!(Vec::<u8, _>::try_with_capacity_in(100, alloc.clone()).is_err());
+        let zeroed = alloc.allocate_zeroed(Layout::new::<u32>()).unwrap();
+        assert_eq!(counters.allocated_bytes.load(Ordering::Relaxed), 12);
+        assert_eq!(counters.failures.load(Ordering::Relaxed), 1);
+
+        drop(value);
+        unsafe { alloc.deallocate(zeroed.as_non_null_ptr(), Layout::new::<u32>()) };
+        assert!(alloc.try_reset());
+        assert_eq!(counters.resets.load(Ordering::Relaxed), 1);
+    }
+}
@@ -0,0 +1,230 @@
+use core::{
+    alloc::{AllocError, Allocator, Layout},
+    cell::UnsafeCell,
+    mem::MaybeUninit,
+    ptr::NonNull,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+/// arena whose memory pool is an `N` byte array stored inside itself, so it can live in a `static` or on the stack
+/// without any heap involvement, e.g. in firmware. the positions of the blocks are computed from the address of the
+/// arena on every allocation, so it can be moved freely while nothing is borrowed from it.
+/// `&InlineArena` implements `Allocator`, only the last block is reclaimed on deallocation.
+///
+/// ```
+/// #![feature(allocator_api)]
+/// use xs_arena_allocator::InlineArena;
+///
+/// static SCRATCH: InlineArena<1024> = InlineArena::new();
+///
+/// let mut values = Vec::new_in(&SCRATCH);
+/// values.extend([1u32, 2, 3]);
+/// assert!(SCRATCH.used_space() >= 12);
+/// ```
+pub struct InlineArena<const N: usize> {
+    buf: UnsafeCell<[MaybeUninit<u8>; N]>,
+    offset: AtomicUsize,
+}
+
+// the blocks handed out are disjoint, the offset is only moved with atomic operations. handing a block back
+// releases the writes to it and taking it acquires them, so reused blocks are ordered between threads.
+unsafe impl<const N: usize> Sync for InlineArena<N> {}
+
+impl<const N: usize> InlineArena<N> {
+    pub const fn new() -> Self {
+        Self {
+            buf: UnsafeCell::new([MaybeUninit::uninit(); N]),
+            offset: AtomicUsize::new(0),
+        }
+    }
+
+    /// returns the maximum capacity of the arena, including the space thats already used.
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// returns the available space of the arena in bytes.
+    pub fn available_space(&self) -> usize {
+        N - self.used_space()
+    }
+
+    /// returns the used space of the arena in bytes, including the padding needed for alignment.
+    pub fn used_space(&self) -> usize {
+        self.offset.load(Ordering::Relaxed)
+    }
+
+    /// rewinds the arena to its initial state so the memory pool can be reused.
+    pub fn reset(&mut self) {
+        *self.offset.get_mut() = 0;
+    }
+
+    /// returns a pointer to a memory slice with the size and alignment of the passed `Layout`.
+    pub fn get_next_mem_slice(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        let base = self.base();
+        let mut offset = self.offset.load(Ordering::Acquire);
+        loop {
+            let align_offset = unsafe { base.add(offset) }.align_offset(layout.align());
+            let start = offset.checked_add(align_offset).ok_or(AllocError)?;
+            let end = start.checked_add(layout.size()).ok_or(AllocError)?;
+            if end > N {
+                return Err(AllocError);
+            }
+
+            match self
+                .offset
+                .compare_exchange_weak(offset, end, Ordering::AcqRel, Ordering::Acquire)
+            {
+                Ok(_) => return Ok(self.block(start, layout.size())),
+                Err(current) => offset = current,
+            }
+        }
+    }
+
+    fn base(&self) -> *mut u8 {
+        self.buf.get().cast()
+    }
+
+    fn block(&self, start: usize, len: usize) -> NonNull<[u8]> {
+        let ptr = unsafe { NonNull::new_unchecked(self.base().add(start)) };
+        NonNull::slice_from_raw_parts(ptr, len)
+    }
+
+    /// returns the offset of the end of the block at `ptr` with `size` bytes.
+    fn end_of(&self, ptr: NonNull<u8>, size: usize) -> usize {
+        ptr.as_ptr().addr() - self.base().addr() + size
+    }
+}
+
+impl<const N: usize> Default for InlineArena<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+unsafe impl<const N: usize> Allocator for &InlineArena<N> {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.get_next_mem_slice(layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        let end = self.end_of(ptr, layout.size());
+        let start = end - layout.size();
+        let _ = self
+            .offset
+            .compare_exchange(end, start, Ordering::AcqRel, Ordering::Acquire);
+    }
+
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        let end = self.end_of(ptr, old_layout.size());
+        let start = end - old_layout.size();
+        let new_end = start.checked_add(new_layout.size()).ok_or(AllocError)?;
+
+        // the last block grows in place if it is aligned for the new layout.
+        if ptr.as_ptr().addr().is_multiple_of(new_layout.align())
+            && new_end <= N
+            && self
+                .offset
+                .compare_exchange(end, new_end, Ordering::AcqRel, Ordering::Acquire)
+                .is_ok()
+        {
+            return Ok(self.block(start, new_layout.size()));
+        }
+
+        let new_block = self.allocate(new_layout)?;
+        unsafe {
+            new_block
+                .as_mut_ptr()
+                .copy_from_nonoverlapping(ptr.as_ptr(), old_layout.size());
+            self.deallocate(ptr, old_layout);
+        }
+        Ok(new_block)
+    }
+
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        if !ptr.as_ptr().addr().is_multiple_of(new_layout.align()) {
+            let new_block = self.allocate(new_layout)?;
+            unsafe {
+                new_block
+                    .as_mut_ptr()
+                    .copy_from_nonoverlapping(ptr.as_ptr(), new_layout.size());
+                self.deallocate(ptr, old_layout);
+            }
+            return Ok(new_block);
+        }
+
+        // the tail of the last block is handed back.
+        let end = self.end_of(ptr, old_layout.size());
+        let new_end = end - old_layout.size() + new_layout.size();
+        let _ = self
+            .offset
+            .compare_exchange(end, new_end, Ordering::AcqRel, Ordering::Acquire);
+        Ok(NonNull::slice_from_raw_parts(ptr, new_layout.size()))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::{boxed::Box, vec::Vec};
+
+    use super::*;
+
+    #[test]
+    fn allocates_from_inline_storage() {
+        let mut arena = InlineArena::<64>::new();
+        {
+            let mut values = Vec::with_capacity_in(2, &arena);
+            values.extend([1u64, 2]);
+            let start = values.as_ptr();
+
+            // the last block grows in place
+            values.push(3);
+            assert_eq!(values.as_ptr(), start);
+            assert_eq!(values, [1, 2, 3]);
+
+            assert!(Box::try_new_in([0u8; 64], &arena).is_err());
+            let boxed = Box::new_in(7u8, &arena);
+            assert_eq!(*boxed, 7);
+        }
+        // blocks freed in reverse order are all handed back, only the alignment padding stays used
+        assert!(arena.used_space() < 8);
+
+        arena.reset();
+        assert_eq!(arena.available_space(), arena.capacity());
+    }
+
+    #[test]
+    fn blocks_are_reused_across_threads() {
+        // large enough for every block, interleaved frees of blocks that aren't the last one are not reclaimed
+        static SHARED: InlineArena<512> = InlineArena::new();
+        std::thread::scope(|scope| {
+            for id in 0..4u64 {
+                scope.spawn(move || {
+                    for round in 0..8 {
+                        // the block is handed back right away, so the threads keep reusing each other's memory
+                        let value = Box::new_in(id * 100 + round, &SHARED);
+                        assert_eq!(*value, id * 100 + round);
+                    }
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn moves_while_unborrowed() {
+        let arena = InlineArena::<32>::new();
+        let moved = Box::new(arena);
+        let block = moved.get_next_mem_slice(Layout::new::<u32>()).unwrap();
+        let base = moved.base().addr();
+        assert!((base..base + 32).contains(&block.as_mut_ptr().addr()));
+    }
+}
@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+
+use crate::{sync::Mutex, Arena};
+
+/// compact id of a string interned by an `Interner`. ids are handed out in interning order starting at 0.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Symbol(u32);
+
+impl Symbol {
+    /// returns the position of the symbol in interning order, e.g. to index a side table.
+    pub fn index(self) -> usize {
+        self.0 as usize
+    }
+}
+
+/// stores every distinct string once in an arena and hands out `Symbol`s or references to the stored copy.
+/// the lookup tables live on the global heap, only the string contents are copied into the arena.
+/// the arena stays borrowed as long as the interner lives, so it can't be reset underneath the strings.
+pub struct Interner<'a> {
+    arena: &'a Arena,
+    tables: Mutex<Tables<'a>>,
+}
+
+struct Tables<'a> {
+    symbols: HashMap<&'a str, Symbol>,
+    strings: Vec<&'a str>,
+}
+
+impl<'a> Interner<'a> {
+    pub fn new(arena: &'a Arena) -> Self {
+        Self {
+            arena,
+            tables: Mutex::new(Tables {
+                symbols: HashMap::new(),
+                strings: Vec::new(),
+            }),
+        }
+    }
+
+    /// returns the symbol of `string`, copying it into the arena if it wasn't interned before.
+    ///
+    /// # Panics
+    /// calls `handle_alloc_error` if the arena is exhausted and panics if more than `u32::MAX` strings are interned.
+    pub fn intern(&self, string: &str) -> Symbol {
+        let mut tables = self.tables.lock();
+        if let Some(&symbol) = tables.symbols.get(string) {
+            return symbol;
+        }
+
+        let symbol = Symbol(u32::try_from(tables.strings.len()).expect("too many interned strings"));
+        let string: &'a str = self.arena.alloc_str(string);
+        tables.strings.push(string);
+        tables.symbols.insert(string, symbol);
+        symbol
+    }
+
+    /// same as `intern` but returns the copy in the arena, which compares equal for equal strings.
+    pub fn intern_str(&self, string: &str) -> &'a str {
+        self.resolve(self.intern(string))
+    }
+
+    /// returns the symbol of `string` if it was interned before.
+    pub fn get(&self, string: &str) -> Option<Symbol> {
+        self.tables.lock().symbols.get(string).copied()
+    }
+
+    /// returns the string of `symbol`.
+    ///
+    /// # Panics
+    /// panics if `symbol` was created by another interner that interned more strings than this one.
+    pub fn resolve(&self, symbol: Symbol) -> &'a str {
+        self.tables.lock().strings[symbol.index()]
+    }
+
+    /// returns the amount of distinct strings that were interned.
+    pub fn len(&self) -> usize {
+        self.tables.lock().strings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn deduplicates_strings() {
+        let arena = Arena::new(1024);
+        let interner = Interner::new(&arena);
+
+        let foo = interner.intern("foo");
+        let bar = interner.intern("bar");
+        assert_ne!(foo, bar);
+        assert_eq!(interner.intern(&String::from("foo")), foo);
+        assert_eq!((foo.index(), bar.index()), (0, 1));
+        assert_eq!(interner.resolve(bar), "bar");
+        assert_eq!(interner.get("baz"), None);
+
+        // only the first copy of every string is stored in the arena
+        assert_eq!(arena.used_space(), 6);
+        assert!(std::ptr::eq(interner.intern_str("foo"), interner.resolve(foo)));
+        assert_eq!(interner.len(), 2);
+    }
+
+    #[test]
+    fn shared_between_threads() {
+        let arena = Arena::new(4096);
+        let interner = Interner::new(&arena);
+
+        let symbols: Vec<Vec<Symbol>> = std::thread::scope(|scope| {
+            let handles: Vec<_> = (0..4)
+                .map(|_| scope.spawn(|| (0..32).map(|i| interner.intern(&i.to_string())).collect()))
+                .collect();
+            handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+        });
+
+        assert!(symbols.windows(2).all(|pair| pair[0] == pair[1]));
+        assert_eq!(interner.len(), 32);
+    }
+}
@@ -1,20 +1,474 @@
+#![cfg_attr(not(any(test, feature = "std")), no_std)]
 #![feature(allocator_api)]
 #![feature(slice_ptr_get)]
+#![feature(strict_provenance_lints)]
+#![deny(fuzzy_provenance_casts, lossy_provenance_casts)]
+#![cfg_attr(feature = "asan", feature(cfg_sanitize))]
 
-use std::{
+extern crate alloc;
+
+use alloc::{boxed::Box, sync::Arc, vec::Vec};
+use core::{
     alloc::{AllocError, Allocator, Layout},
-    ptr::NonNull,
-    sync::{
-        atomic::{AtomicUsize, Ordering},
-        Arc,
-    },
+    fmt,
+    mem::MaybeUninit,
+    ptr::{self, NonNull},
+};
+
+use checkpoint::RollbackLog;
+#[cfg(feature = "std")]
+use std::io;
+#[cfg(feature = "track-threads")]
+use std::{
+    collections::HashMap,
+    thread::{self, ThreadId},
 };
+use sync::{
+    atomic::{AtomicPtr, AtomicU8, AtomicUsize, Ordering},
+    CachePadded, Mutex,
+};
+use typed::DropEntry;
+
+#[cfg(feature = "allocator-api2")]
+mod api2;
+#[cfg(feature = "rkyv")]
+mod archive;
+#[cfg(feature = "asan")]
+mod asan;
+mod back;
+mod batch;
+#[cfg(feature = "bytes")]
+mod buf;
+mod builder;
+mod checkpoint;
+mod collections;
+#[cfg(feature = "serde")]
+mod de;
+#[cfg(feature = "metrics")]
+mod exporter;
+mod fallback;
+mod frame;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod global;
+#[cfg(feature = "std")]
+mod growable;
+mod handle;
+#[cfg(feature = "hashbrown")]
+mod hash;
+mod hooks;
+mod inline;
+#[cfg(feature = "std")]
+mod interner;
+mod local;
+mod pool;
+#[cfg(feature = "profile")]
+mod profile;
+#[cfg(all(unix, feature = "std"))]
+mod secure;
+#[cfg(feature = "std")]
+mod sharded;
+mod slab;
+mod split;
+#[cfg(feature = "std")]
+mod snapshot;
+mod sync;
+#[cfg(all(any(unix, windows), feature = "std"))]
+mod sys;
+#[cfg(feature = "std")]
+mod task;
+#[cfg(feature = "std")]
+mod thread_local;
+mod typed;
+#[cfg(all(any(unix, windows), feature = "std"))]
+mod virtual_arena;
+#[cfg(target_arch = "wasm32")]
+mod wasm;
+
+#[cfg(feature = "rkyv")]
+pub use archive::ARCHIVE_ALIGN;
+pub use back::BackArenaAllocator;
+pub use batch::AllocationBatch;
+#[cfg(feature = "bytes")]
+pub use buf::ArenaBuf;
+pub use builder::{ArenaBuilder, BuiltArena, DEFAULT_CAPACITY};
+pub use checkpoint::{ArenaMark, Checkpoint};
+#[doc(hidden)]
+pub use collections::__private;
+pub use collections::{prelude, ArenaString, ArenaVec};
+#[cfg(feature = "serde")]
+pub use de::{ArenaSeed, DeserializeIn};
+#[cfg(feature = "metrics")]
+pub use exporter::ArenaMetrics;
+pub use fallback::FallbackAllocator;
+pub use frame::FrameArena;
+pub use global::StaticArenaAlloc;
+#[cfg(feature = "std")]
+pub use growable::{GrowableArena, GrowableArenaAllocator, GrowthPolicy};
+pub use handle::Handle;
+#[cfg(feature = "hashbrown")]
+pub use hash::{ArenaHashMap, ArenaHashSet};
+pub use hooks::ArenaHooks;
+pub use inline::InlineArena;
+#[cfg(feature = "std")]
+pub use interner::{Interner, Symbol};
+pub use local::{LocalArena, LocalArenaAllocator};
+pub use pool::{ArenaPool, PooledArena};
+#[cfg(feature = "profile")]
+pub use profile::{CallSite, ProfileReport};
+#[cfg(all(unix, feature = "std"))]
+pub use secure::SecureArena;
+#[cfg(feature = "std")]
+pub use sharded::ShardedArenaAllocator;
+pub use slab::{SlabArena, SlabBox};
+pub use split::SubArena;
+#[cfg(feature = "std")]
+pub use task::ArenaScope;
+#[cfg(feature = "std")]
+pub use thread_local::{with_thread_arena, ThreadLocalArena};
+#[cfg(all(any(unix, windows), feature = "std"))]
+pub use virtual_arena::VirtualArena;
+#[cfg(target_arch = "wasm32")]
+pub use wasm::WASM_PAGE_SIZE;
+
+/// byte pattern that newly handed out memory is filled with when the `debug-fill` feature is enabled.
+#[cfg(feature = "debug-fill")]
+pub const DEBUG_FILL_ALLOCATED: u8 = 0xAA;
+
+/// byte pattern that the memory pool is filled with on reset and drop when the `debug-fill` feature is enabled.
+#[cfg(feature = "debug-fill")]
+pub const DEBUG_FILL_FREED: u8 = 0xDD;
+
+/// allocations larger than this are never rounded up by a `RoundingPolicy`.
+pub const MAX_ROUNDED_SIZE: usize = 64 * 1024;
+
+/// controls how the size of an allocation is rounded up before it gets reserved.
+/// the whole rounded block is returned to the caller, so collections like `Vec` can grow into it without reallocating.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum RoundingPolicy {
+    /// allocations reserve exactly the requested size.
+    #[default]
+    Off,
+    /// allocation sizes are rounded up to the next power of two.
+    NextPowerOfTwo,
+    /// allocation sizes are rounded up to the next multiple of the passed quantum.
+    Quantum(usize),
+    /// allocations smaller than the passed size are raised to it, e.g. so that tiny vectors and strings start with
+    /// room to grow. larger allocations reserve exactly the requested size.
+    Minimum(usize),
+}
+
+impl RoundingPolicy {
+    /// returns the rounded size or `None` if rounding overflowed.
+    fn round(self, size: usize) -> Option<usize> {
+        if size == 0 || size > MAX_ROUNDED_SIZE {
+            return Some(size);
+        }
+
+        match self {
+            RoundingPolicy::Off => Some(size),
+            RoundingPolicy::NextPowerOfTwo => size.checked_next_power_of_two(),
+            RoundingPolicy::Quantum(0) => Some(size),
+            RoundingPolicy::Quantum(quantum) => size.checked_next_multiple_of(quantum),
+            RoundingPolicy::Minimum(minimum) => Some(size.max(minimum.min(MAX_ROUNDED_SIZE))),
+        }
+    }
+}
+
+/// controls what the arena does when it is reset or dropped while allocations are still outstanding,
+/// e.g. because an arena-backed `Vec` is still alive.
+/// values allocated with the typed helpers like `Arena::alloc` are never deallocated and count as outstanding,
+/// so this is meant for arenas that hand out memory through `Allocator`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LeakPolicy {
+    /// outstanding allocations are silently discarded.
+    #[default]
+    Ignore,
+    /// outstanding allocations are added to `ArenaStats::leaked_allocations` on reset.
+    Report,
+    /// a warning with the amount of outstanding allocations is printed to stderr.
+    #[cfg(feature = "std")]
+    Warn,
+    /// panics in builds with debug assertions and is ignored otherwise.
+    DebugPanic,
+}
+
+/// the reason why the arena failed to hand out memory.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ArenaErrorKind {
+    /// there is not enough space left in the memory pool.
+    OutOfSpace = 1,
+    /// no address inside the memory pool satisfies the requested alignment.
+    AlignmentUnsatisfiable = 2,
+    /// the size calculations for the allocation overflowed.
+    Overflow = 3,
+    /// the memory pool of a new arena couldn't be allocated.
+    PoolAllocationFailed = 4,
+    /// the operating system refused to map the memory pool of a new arena, with the raw error code,
+    /// e.g. `ENOMEM` or `EINVAL`.
+    Os(i32) = 5,
+}
+
+impl ArenaErrorKind {
+    /// converts the error kind to the value stored in an atomic. only the failures of allocations are stored, which
+    /// never come from the operating system.
+    fn to_u8(self) -> u8 {
+        match self {
+            ArenaErrorKind::OutOfSpace => 1,
+            ArenaErrorKind::AlignmentUnsatisfiable => 2,
+            ArenaErrorKind::Overflow => 3,
+            ArenaErrorKind::PoolAllocationFailed | ArenaErrorKind::Os(_) => 4,
+        }
+    }
+
+    /// converts the value stored in an atomic back to the error kind, where 0 means no error.
+    fn from_u8(value: u8) -> Option<Self> {
+        match value {
+            1 => Some(ArenaErrorKind::OutOfSpace),
+            2 => Some(ArenaErrorKind::AlignmentUnsatisfiable),
+            3 => Some(ArenaErrorKind::Overflow),
+            4 => Some(ArenaErrorKind::PoolAllocationFailed),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for ArenaErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ArenaErrorKind::OutOfSpace => "the arena is out of space",
+            ArenaErrorKind::AlignmentUnsatisfiable => "the alignment can't be satisfied by the memory pool",
+            ArenaErrorKind::Overflow => "the size of the allocation overflowed",
+            ArenaErrorKind::PoolAllocationFailed => "the memory pool couldn't be allocated",
+            #[cfg(feature = "std")]
+            ArenaErrorKind::Os(code) => {
+                return write!(f, "the memory pool couldn't be mapped: {}", io::Error::from_raw_os_error(*code))
+            }
+            #[cfg(not(feature = "std"))]
+            ArenaErrorKind::Os(code) => return write!(f, "the memory pool couldn't be mapped: os error {code}"),
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for ArenaErrorKind {
+    fn from(err: io::Error) -> Self {
+        err.raw_os_error().map_or(ArenaErrorKind::PoolAllocationFailed, ArenaErrorKind::Os)
+    }
+}
+
+/// describes a failed allocation in more detail than `AllocError`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ArenaError {
+    /// the layout that was requested.
+    pub layout: Layout,
+    /// the space that was left in the arena when the allocation failed.
+    pub available: usize,
+    /// the reason of the failure.
+    pub kind: ArenaErrorKind,
+}
+
+impl fmt::Display for ArenaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "failed to allocate {} bytes aligned to {} with {} bytes available: {}",
+            self.layout.size(),
+            self.layout.align(),
+            self.available,
+            self.kind
+        )
+    }
+}
+
+impl core::error::Error for ArenaError {}
+
+impl From<ArenaError> for AllocError {
+    fn from(_: ArenaError) -> Self {
+        AllocError
+    }
+}
+
+/// options for arenas whose memory pool is mapped directly from the operating system.
+#[cfg(all(any(unix, windows), feature = "std"))]
+#[derive(Clone, Debug, Default)]
+pub struct MmapOptions {
+    commit_step: Option<usize>,
+    align: usize,
+    huge_pages: bool,
+    guard_pages: bool,
+    numa: Option<NumaPolicy>,
+    trim_on_reset: Option<usize>,
+}
+
+/// selects the NUMA nodes the pages of a memory pool are placed on.
+#[cfg(all(any(unix, windows), feature = "std"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NumaPolicy {
+    /// every page is placed on the passed node.
+    Node(usize),
+    /// the pages are spread evenly over all nodes the process may use.
+    Interleave,
+}
+
+#[cfg(all(any(unix, windows), feature = "std"))]
+impl MmapOptions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// only keeps the memory up to the offset plus roughly `commit_step` bytes accessible and commits more in steps
+    /// of `commit_step` as the offset advances. the step is rounded up to the page size.
+    /// without a commit step the whole pool is accessible and the operating system commits every page on first access.
+    pub fn commit_step(mut self, commit_step: usize) -> Self {
+        self.commit_step = Some(commit_step);
+        self
+    }
+
+    /// aligns the start of the memory pool to `align` bytes, which has to be a power of two.
+    /// mappings are always page aligned, larger alignments like 2 MiB for huge page aligned buffers
+    /// are satisfied by over-reserving address space instead of wasting padding inside the arena.
+    pub fn align(mut self, align: usize) -> Self {
+        self.align = align;
+        self
+    }
+
+    /// backs the memory pool with 2 MiB transparent huge pages via `madvise(MADV_HUGEPAGE)`, which reduces TLB misses
+    /// for workloads that touch large parts of the arena. the pool is aligned and sized to multiples of 2 MiB.
+    /// creating the arena fails with `ErrorKind::Unsupported` on systems other than linux.
+    pub fn huge_pages(mut self, huge_pages: bool) -> Self {
+        self.huge_pages = huge_pages;
+        self
+    }
+
+    /// places an inaccessible guard page right before and after the memory pool, so that stray reads and writes
+    /// past the ends of the pool fault immediately instead of silently corrupting neighboring memory.
+    pub fn guard_pages(mut self, guard_pages: bool) -> Self {
+        self.guard_pages = guard_pages;
+        self
+    }
+
+    /// places the pages of the memory pool on the NUMA nodes selected by `policy` via `mbind`, so threads bump
+    /// allocating from the arena only touch memory of their own node. creating the arena fails with
+    /// `ErrorKind::Unsupported` on systems other than linux.
+    pub fn numa(mut self, policy: NumaPolicy) -> Self {
+        self.numa = Some(policy);
+        self
+    }
+
+    /// gives the pages beyond the first `keep` bytes back to the system on every reset, see `Arena::trim`.
+    /// this keeps long-lived arenas from holding on to the memory of a burst that isn't touched again.
+    pub fn trim_on_reset(mut self, keep: usize) -> Self {
+        self.trim_on_reset = Some(keep);
+        self
+    }
+}
+
+/// snapshot of the allocation statistics of an arena since it was created or reset.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct ArenaStats {
+    /// amount of successful allocations.
+    pub allocations: usize,
+    /// amount of allocations that failed.
+    pub failed_allocations: usize,
+    /// sum of the sizes of all successful allocations as requested by their layout.
+    pub requested_bytes: usize,
+    /// bytes the successful allocations took from the memory pool, including alignment padding and rounding.
+    pub consumed_bytes: usize,
+    /// the highest offset the arena ever reached, which is the capacity that would have been enough.
+    pub peak_offset: usize,
+    /// allocations that were still outstanding when the arena was reset with `LeakPolicy::Report`.
+    /// unlike the other counters this one is not cleared by `reset`.
+    pub leaked_allocations: usize,
+}
+
+/// counters behind `ArenaStats`.
+#[derive(Default)]
+struct StatCounters {
+    allocations: AtomicUsize,
+    failed_allocations: AtomicUsize,
+    requested_bytes: AtomicUsize,
+    consumed_bytes: AtomicUsize,
+    /// only updated before the offset moves backwards, the current offset is taken into account when reading it.
+    peak_offset: AtomicUsize,
+}
+
+/// metadata of a live allocation, recorded when the `track-allocations` feature is enabled.
+#[cfg(feature = "track-allocations")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AllocationInfo {
+    /// start of the allocation relative to the start of the memory pool.
+    pub offset: usize,
+    /// size of the reserved block in bytes.
+    pub size: usize,
+    /// alignment that was requested for the allocation.
+    pub align: usize,
+}
 
 /// allocates a memory pool during construction and only de-allocates it during `drop()`.
 /// chunks of the memory pool can be requested until nothing is left, which makes every consecutive call fail.
 pub struct Arena {
     mem_pool: NonNull<[u8]>,
-    offset: AtomicUsize,
+    requested_capacity: usize,
+    backing: Backing,
+    /// bumped by every allocation from the front, so it gets a cache line of its own.
+    offset: CachePadded<AtomicUsize>,
+    /// start of the allocations made from the high end of the memory pool with `alloc_back`.
+    back: AtomicUsize,
+    /// the value of `back` before each allocation from the back that still holds its space, the last one belongs to
+    /// the block at `back`. `back` is only moved while this is locked.
+    back_ends: Mutex<Vec<usize>>,
+    /// the arena never reclaims memory below this offset on its own, since there are marks pointing into it.
+    frozen: AtomicUsize,
+    rollbacks: Mutex<RollbackLog>,
+    /// changes whenever the arena is reset, which invalidates every `Handle` issued before.
+    generation: AtomicUsize,
+    wasted_to_alignment: AtomicUsize,
+    live_allocations: AtomicUsize,
+    last_error: AtomicU8,
+    stats: StatCounters,
+    rounding: RoundingPolicy,
+    leak_policy: LeakPolicy,
+    leaked_allocations: AtomicUsize,
+    /// most recently registered destructor of a value allocated with `alloc_droppable`.
+    drop_list: AtomicPtr<DropEntry>,
+    #[cfg(feature = "track-allocations")]
+    allocations: Mutex<Vec<AllocationInfo>>,
+    #[cfg(feature = "track-threads")]
+    allocations_by_thread: Mutex<HashMap<ThreadId, usize>>,
+    #[cfg(feature = "profile")]
+    call_sites: Mutex<profile::CallSites>,
+}
+
+/// describes who owns the memory pool and how it has to be released.
+enum Backing {
+    /// the memory pool was leaked from a boxed slice and is freed on drop.
+    Boxed,
+    /// the memory pool was allocated from the global allocator with the passed layout and is freed on drop.
+    Aligned(Layout),
+    /// the memory pool is owned by somebody else and is left untouched on drop.
+    External,
+    /// the memory pool was mapped from the operating system and is unmapped on drop.
+    /// `guard` bytes of inaccessible address space before and after the pool are unmapped with it.
+    /// `trim_on_reset` is the amount of bytes that are kept when the pool is trimmed on reset.
+    #[cfg(all(any(unix, windows), feature = "std"))]
+    Mmap {
+        commit: Option<Commit>,
+        guard: usize,
+        trim_on_reset: Option<usize>,
+    },
+    /// the memory pool was grown at the end of the wasm linear memory, which can't shrink, and is leaked on drop.
+    /// only the first `committed` bytes exist so far.
+    #[cfg(target_arch = "wasm32")]
+    Wasm { committed: Mutex<usize> },
+}
+
+/// state of a memory pool that gets committed in steps.
+#[cfg(all(any(unix, windows), feature = "std"))]
+struct Commit {
+    step: usize,
+    committed: AtomicUsize,
 }
 
 unsafe impl Send for Arena {}
@@ -22,146 +476,2155 @@ unsafe impl Sync for Arena {}
 
 impl Arena {
     /// create a new arena with the passed capacity in bytes.
+    /// the memory pool is not zeroed up front, so creating large arenas is cheap.
+    /// use `allocate_zeroed` for allocations that have to start out zeroed.
     pub fn new(capacity: usize) -> Self {
+        Self::from_uninit_boxed_slice(Box::new_uninit_slice(capacity))
+    }
+
+    /// same as `new` but returns an error instead of aborting if the memory pool can't be allocated,
+    /// so callers can fall back to a smaller arena or reject the work.
+    pub fn try_new(capacity: usize) -> Result<Self, ArenaError> {
+        let error = |layout, kind| ArenaError {
+            layout,
+            available: 0,
+            kind,
+        };
+
+        // the layout of a single byte is reported if the capacity can't be described by a layout.
+        let layout = Layout::array::<u8>(capacity).map_err(|_| error(Layout::new::<u8>(), ArenaErrorKind::Overflow))?;
+        Box::try_new_uninit_slice(capacity)
+            .map(Self::from_uninit_boxed_slice)
+            .map_err(|_| error(layout, ArenaErrorKind::PoolAllocationFailed))
+    }
+
+    /// create a new arena that uses the passed buffer as its memory pool without zeroing it first.
+    /// allocations hand out possibly uninitialized memory, which is what the `Allocator` contract allows anyway.
+    /// `Allocator::allocate_zeroed` still zeroes the returned block on demand.
+    pub fn from_uninit_boxed_slice(buf: Box<[MaybeUninit<u8>]>) -> Self {
+        Self::from_mem_pool(unsafe { NonNull::new_unchecked(Box::into_raw(buf) as *mut [u8]) }, Backing::Boxed)
+    }
+
+    /// create a new arena that takes ownership of the passed buffer and uses it as its memory pool.
+    pub fn from_boxed_slice(buf: Box<[u8]>) -> Self {
+        Self::from_mem_pool(unsafe { NonNull::new_unchecked(Box::into_raw(buf)) }, Backing::Boxed)
+    }
+
+    /// create a new arena whose memory pool starts at an address that is a multiple of `align`.
+    /// the padding used to align allocations then no longer depends on where the memory pool was placed,
+    /// so the same sequence of allocations with alignments up to `align` always consumes the same amount of space.
+    /// common choices are 64 for cache lines, 32 or 64 for SIMD types and 4096 for pages.
+    /// allocations whose sizes are multiples of their alignment never need padding in such an arena.
+    ///
+    /// # Panics
+    /// panics if `align` is not a power of two.
+    pub fn new_aligned(capacity: usize, align: usize) -> Self {
+        let layout = Layout::from_size_align(capacity, align).expect("invalid alignment for the memory pool");
+        Self::try_new_aligned(capacity, align).unwrap_or_else(|_| alloc::alloc::handle_alloc_error(layout))
+    }
+
+    /// same as `new_aligned` but returns an error instead of panicking or aborting.
+    pub(crate) fn try_new_aligned(capacity: usize, align: usize) -> Result<Self, ArenaError> {
+        let error = |layout, kind| ArenaError {
+            layout,
+            available: 0,
+            kind,
+        };
+        let layout = Layout::from_size_align(capacity, align)
+            .map_err(|_| error(Layout::new::<u8>(), ArenaErrorKind::AlignmentUnsatisfiable))?;
+
+        let base = if capacity == 0 {
+            // a dangling pointer is aligned to the alignment of the type, so it has to be created from the layout.
+            unsafe { NonNull::new_unchecked(ptr::without_provenance_mut(align)) }
+        } else {
+            let ptr = unsafe { alloc::alloc::alloc(layout) };
+            NonNull::new(ptr).ok_or_else(|| error(layout, ArenaErrorKind::PoolAllocationFailed))?
+        };
+
+        Ok(Self::from_mem_pool(NonNull::slice_from_raw_parts(base, capacity), Backing::Aligned(layout)))
+    }
+
+    /// create a new arena that reserves `capacity` bytes of address space and lets the operating system commit
+    /// pages lazily on first access. startup cost and resident memory only depend on the used part of the arena,
+    /// which makes multi-GB arenas cheap. the capacity is rounded up to the page size.
+    /// windows has no lazy commit, so there the whole pool is committed up front. use a commit step or
+    /// `VirtualArena` for large arenas on windows.
+    #[cfg(all(any(unix, windows), feature = "std"))]
+    pub fn new_mmap(capacity: usize) -> io::Result<Self> {
+        Self::new_mmap_with(capacity, MmapOptions::new())
+    }
+
+    /// create a new arena whose memory pool is mapped directly from the operating system.
+    /// the capacity is rounded up to the page size, or to 2 MiB with huge pages. no memory is touched up front,
+    /// which makes this suitable for large arenas that are only partially used.
+    #[cfg(all(any(unix, windows), feature = "std"))]
+    pub fn new_mmap_with(capacity: usize, options: MmapOptions) -> io::Result<Self> {
+        let page_size = if options.huge_pages {
+            sys::HUGE_PAGE_SIZE
+        } else {
+            sys::page_size()
+        };
+        let len = capacity.max(1).checked_next_multiple_of(page_size).ok_or(io::ErrorKind::InvalidInput)?;
+        let commit_step = options
+            .commit_step
+            .map(|step| step.max(1).checked_next_multiple_of(page_size).ok_or(io::ErrorKind::InvalidInput))
+            .transpose()?;
+
+        if options.align != 0 && !options.align.is_power_of_two() {
+            return Err(io::ErrorKind::InvalidInput.into());
+        }
+
+        let align = if options.huge_pages {
+            options.align.max(sys::HUGE_PAGE_SIZE)
+        } else {
+            options.align
+        };
+        let guard = if options.guard_pages { sys::page_size() } else { 0 };
+        let base = sys::reserve_aligned(len, align, guard, commit_step.is_none())?;
+        let release = || unsafe { sys::release(base.as_ptr().sub(guard), len + 2 * guard) };
+
+        if options.huge_pages {
+            if let Err(err) = unsafe { sys::advise_huge_pages(base.as_ptr(), len) } {
+                release();
+                return Err(err);
+            }
+        }
+
+        if let Some(policy) = options.numa {
+            if let Err(err) = unsafe { sys::set_numa_policy(base.as_ptr(), len, policy) } {
+                release();
+                return Err(err);
+            }
+        }
+
+        let mem_pool = NonNull::slice_from_raw_parts(base, len);
+
+        let commit = match commit_step {
+            Some(step) => {
+                let committed = step.min(len);
+                if let Err(err) = unsafe { sys::commit(base.as_ptr(), committed) } {
+                    release();
+                    return Err(err);
+                }
+
+                Some(Commit {
+                    step,
+                    committed: AtomicUsize::new(committed),
+                })
+            }
+            None => None,
+        };
+
+        let backing = Backing::Mmap {
+            commit,
+            guard,
+            trim_on_reset: options.trim_on_reset,
+        };
+        let mut arena = Self::from_mem_pool(mem_pool, backing);
+        arena.requested_capacity = capacity;
+        Ok(arena)
+    }
+
+    /// create a new arena on top of memory that is owned by somebody else, e.g. a block allocated from another arena.
+    /// this allows carving scratch arenas out of a long-lived allocation, which can be reset independently
+    /// while the owner of the memory only sees a single allocation.
+    /// the memory is not freed when the arena is dropped.
+    ///
+    /// # Safety
+    /// `mem` must be valid for reads and writes for its whole length and must not be accessed by anything else
+    /// while the arena exists. this means the arena must not outlive the allocation it was created from.
+    pub unsafe fn from_external(mem: NonNull<[u8]>) -> Self {
+        Self::from_mem_pool(mem, Backing::External)
+    }
+
+    /// create a new arena on top of `len` bytes starting at `ptr`, e.g. a buffer on the stack.
+    /// the memory is not freed when the arena is dropped.
+    ///
+    /// # Safety
+    /// same as `from_external`. additionally `ptr` must not be null.
+    pub unsafe fn from_raw_parts(ptr: *mut u8, len: usize) -> Self {
+        unsafe { Self::from_external(NonNull::slice_from_raw_parts(NonNull::new_unchecked(ptr), len)) }
+    }
+
+    /// create a new arena on top of a buffer that lives for the rest of the program, e.g. a `static`.
+    pub fn from_static(buf: &'static mut [u8]) -> Self {
+        unsafe { Self::from_external(NonNull::from(buf)) }
+    }
+
+    fn from_mem_pool(mem_pool: NonNull<[u8]>, backing: Backing) -> Self {
+        #[cfg(feature = "asan")]
+        asan::poison(mem_pool.as_mut_ptr(), mem_pool.len());
+
         Self {
-            mem_pool: unsafe { NonNull::new_unchecked(Box::into_raw(vec![0; capacity].into_boxed_slice())) },
-            offset: AtomicUsize::new(0),
+            mem_pool,
+            requested_capacity: mem_pool.len(),
+            backing,
+            offset: CachePadded(AtomicUsize::new(0)),
+            back: AtomicUsize::new(mem_pool.len()),
+            back_ends: Mutex::new(Vec::new()),
+            frozen: AtomicUsize::new(0),
+            rollbacks: Mutex::new(RollbackLog::default()),
+            generation: AtomicUsize::new(handle::next_generation()),
+            wasted_to_alignment: AtomicUsize::new(0),
+            live_allocations: AtomicUsize::new(0),
+            last_error: AtomicU8::new(0),
+            stats: StatCounters::default(),
+            rounding: RoundingPolicy::Off,
+            leak_policy: LeakPolicy::Ignore,
+            leaked_allocations: AtomicUsize::new(0),
+            drop_list: AtomicPtr::new(ptr::null_mut()),
+            #[cfg(feature = "track-allocations")]
+            allocations: Mutex::new(Vec::new()),
+            #[cfg(feature = "track-threads")]
+            allocations_by_thread: Mutex::new(HashMap::new()),
+            #[cfg(feature = "profile")]
+            call_sites: Mutex::new(profile::CallSites::new()),
+        }
+    }
+
+    /// sets the policy used to round up the size of every allocation.
+    pub fn with_rounding(mut self, rounding: RoundingPolicy) -> Self {
+        self.rounding = rounding;
+        self
+    }
+
+    /// sets what happens when the arena is reset or dropped while allocations are still outstanding.
+    pub fn with_leak_policy(mut self, leak_policy: LeakPolicy) -> Self {
+        self.leak_policy = leak_policy;
+        self
+    }
+
+    /// rewinds the arena to its initial state so the memory pool can be reused.
+    /// the exclusive borrow guarantees that no references into the memory pool handed out by the arena are alive.
+    /// values allocated with `alloc_droppable` are dropped first.
+    pub fn reset(&mut self) {
+        let leaked = self.check_leaks();
+        self.run_drops(0);
+        #[cfg(feature = "debug-fill")]
+        self.debug_fill_pool();
+        #[cfg(feature = "asan")]
+        self.poison_pool();
+        *self.offset.get_mut() = 0;
+        *self.back.get_mut() = self.capacity();
+        self.back_ends.get_mut().clear();
+        *self.frozen.get_mut() = 0;
+        self.rollbacks.get_mut().record(0);
+        *self.generation.get_mut() = handle::next_generation();
+        *self.wasted_to_alignment.get_mut() = 0;
+        *self.live_allocations.get_mut() = 0;
+        *self.last_error.get_mut() = 0;
+        self.stats = StatCounters::default();
+
+        #[cfg(feature = "track-allocations")]
+        self.allocations.get_mut().clear();
+
+        #[cfg(feature = "track-threads")]
+        self.allocations_by_thread.get_mut().clear();
+
+        #[cfg(feature = "profile")]
+        self.call_sites.get_mut().clear();
+
+        #[cfg(all(any(unix, windows), feature = "std"))]
+        if let Backing::Mmap {
+            trim_on_reset: Some(keep),
+            ..
+        } = self.backing
+        {
+            // releasing the pages is only an optimization, the arena stays usable if it fails.
+            let _ = self.trim_from(keep);
+        }
+
+        if let Some(live) = leaked {
+            leak_panic(live);
+        }
+    }
+
+    /// starts a checkpoint which rolls back every allocation made through it, unless it gets committed.
+    pub fn checkpoint(&mut self) -> Checkpoint<'_> {
+        Checkpoint::new(self)
+    }
+
+    /// resets the arena and gives all memory beyond the first `keep` bytes back to the system.
+    /// this is meant for long-lived arenas that should release their memory during idle periods.
+    /// mmap-backed arenas round `keep` up to the page size. arenas on top of external memory can't be shrunk.
+    /// the arena is reset even if shrinking fails.
+    pub fn reset_and_shrink(&mut self, keep: usize) -> Result<(), AllocError> {
+        self.reset();
+
+        #[cfg(feature = "asan")]
+        self.unpoison_pool();
+        let result = self.shrink_pool(keep);
+        #[cfg(feature = "asan")]
+        self.poison_pool();
+        result
+    }
+
+    /// gives the physical memory of the pages beyond the offset back to the system while keeping the capacity, e.g.
+    /// after a burst that left hundreds of megabytes resident which won't be touched again soon.
+    /// returns the size of the range that was released.
+    /// arenas that commit their memory pool in steps decommit the pages and commit them again once the offset reaches
+    /// them, other mmap-backed arenas keep the pages accessible. arenas that aren't mmap-backed have nothing to release.
+    #[cfg(all(any(unix, windows), feature = "std"))]
+    pub fn trim(&mut self) -> io::Result<usize> {
+        self.trim_from(0)
+    }
+
+    /// releases the pages beyond the first `keep` bytes or the offset, whichever is larger. see `trim`.
+    #[cfg(all(any(unix, windows), feature = "std"))]
+    fn trim_from(&mut self, keep: usize) -> io::Result<usize> {
+        let Backing::Mmap { commit, .. } = &self.backing else {
+            return Ok(0);
+        };
+
+        let page_size = sys::page_size();
+        let start = keep.max(*self.offset.get_mut()).next_multiple_of(page_size);
+        let mut end = *self.back.get_mut() / page_size * page_size;
+        if let Some(commit) = commit {
+            end = end.min(commit.committed.load(Ordering::Relaxed));
+        }
+        if start >= end {
+            return Ok(0);
+        }
+
+        let ptr = unsafe { self.mem_pool.as_mut_ptr().add(start) };
+        match commit {
+            Some(commit) => {
+                unsafe { sys::decommit(ptr, end - start)? };
+                // pages committed for allocations from the back may stay above the new limit, committing them again
+                // later keeps their contents.
+                commit.committed.store(start, Ordering::Relaxed);
+            }
+            None => unsafe { sys::discard(ptr, end - start)? },
+        }
+        Ok(end - start)
+    }
+
+    /// gives all memory beyond the first `keep` bytes back to the system. see `reset_and_shrink`.
+    fn shrink_pool(&mut self, keep: usize) -> Result<(), AllocError> {
+        if keep >= self.capacity() {
+            return Ok(());
         }
+
+        self.requested_capacity = keep;
+
+        let base = self.mem_pool.as_mut_ptr();
+        let new_base = match &mut self.backing {
+            Backing::Boxed if keep == 0 => unsafe {
+                let _: Box<[MaybeUninit<u8>]> = Box::from_raw(self.mem_pool.as_ptr() as *mut [MaybeUninit<u8>]);
+                NonNull::dangling()
+            },
+            Backing::Boxed => unsafe {
+                let layout = Layout::array::<u8>(self.mem_pool.len()).map_err(|_| AllocError)?;
+                NonNull::new(alloc::alloc::realloc(base, layout, keep)).ok_or(AllocError)?
+            },
+            Backing::Aligned(layout) => unsafe {
+                let new_layout = Layout::from_size_align(keep, layout.align()).map_err(|_| AllocError)?;
+                let new_base = if keep == 0 {
+                    alloc::alloc::dealloc(base, *layout);
+                    NonNull::new_unchecked(ptr::without_provenance_mut(layout.align()))
+                } else {
+                    NonNull::new(alloc::alloc::realloc(base, *layout, keep)).ok_or(AllocError)?
+                };
+                *layout = new_layout;
+                new_base
+            },
+            Backing::External => return Err(AllocError),
+            #[cfg(target_arch = "wasm32")]
+            Backing::Wasm { .. } => return Err(AllocError),
+            #[cfg(all(any(unix, windows), feature = "std"))]
+            Backing::Mmap { commit, guard, .. } => {
+                let keep = keep.next_multiple_of(sys::page_size());
+                if keep >= self.mem_pool.len() {
+                    return Ok(());
+                }
+
+                // the first pages of the released tail become the new guard after the pool.
+                unsafe {
+                    if *guard > 0 {
+                        sys::protect(base.add(keep), *guard).map_err(|_| AllocError)?;
+                    }
+                    sys::release_tail(base.add(keep + *guard), self.mem_pool.len() - keep);
+                }
+                if let Some(commit) = commit {
+                    commit.committed.fetch_min(keep, Ordering::Relaxed);
+                }
+                self.mem_pool = NonNull::slice_from_raw_parts(self.mem_pool.as_non_null_ptr(), keep);
+                *self.back.get_mut() = keep;
+                self.back_ends.get_mut().clear();
+                return Ok(());
+            }
+        };
+
+        self.mem_pool = NonNull::slice_from_raw_parts(new_base, keep);
+        *self.back.get_mut() = keep;
+        self.back_ends.get_mut().clear();
+        Ok(())
+    }
+
+    /// returns the start of the memory pool, which the offsets returned by `allocate_with_offset` are relative to.
+    pub fn base(&self) -> NonNull<u8> {
+        self.mem_pool.as_non_null_ptr()
     }
 
     /// returns the maximum capacity of the arena, including the space thats already used.
+    /// this can be larger than the requested capacity if the constructor had to round it up.
     pub fn capacity(&self) -> usize {
         self.mem_pool.len()
     }
 
+    /// returns the capacity that was passed when the arena was created or last shrunk.
+    pub fn requested_capacity(&self) -> usize {
+        self.requested_capacity
+    }
+
     /// returns the available space of the arena in bytes.
     pub fn available_space(&self) -> usize {
-        self.capacity() - self.offset.load(Ordering::Relaxed)
+        self.back.load(Ordering::Relaxed).saturating_sub(self.offset.load(Ordering::Relaxed))
     }
 
-    /// returns a pointer to a memory slice with the size and alignment of the passed `Layout`.
-    pub fn get_next_mem_slice(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
-        let (start, end) = self.get_aligned_memory_bounds(layout)?;
-        unsafe { Ok(self.mem_pool.get_unchecked_mut(start..end)) }
+    /// returns the amount of bytes at the start of the memory pool that are accessible.
+    /// only arenas with a commit step commit less than their capacity.
+    pub fn committed(&self) -> usize {
+        match &self.backing {
+            #[cfg(all(any(unix, windows), feature = "std"))]
+            Backing::Mmap { commit: Some(commit), .. } => commit.committed.load(Ordering::Relaxed),
+            #[cfg(target_arch = "wasm32")]
+            Backing::Wasm { committed } => *committed.lock(),
+            _ => self.capacity(),
+        }
     }
 
-    /// returns the offset start and end for the allocated memory.
-    fn get_aligned_memory_bounds(&self, layout: Layout) -> Result<(usize, usize), AllocError> {
-        let requested_size = layout.size();
-
-        loop {
-            // get a copy of the current value of the offset.
-            // it is important that the value used for the calculations can't be changed by another thread
-            // since we need to very the result with a compare_exchange in the end.
-            let offset = self.offset.load(Ordering::Acquire);
-            let align_offset = unsafe { self.mem_pool.as_mut_ptr().add(offset).align_offset(layout.align()) };
+    /// returns the used space of the arena in bytes, including the padding needed for alignment.
+    pub fn used_space(&self) -> usize {
+        self.offset.load(Ordering::Relaxed) + self.capacity() - self.back.load(Ordering::Relaxed)
+    }
 
-            // failed to find suitable alignment
-            if align_offset == usize::MAX {
-                return Err(AllocError);
-            }
+    /// returns the amount of bytes that were skipped to align allocations.
+    pub fn wasted_to_alignment(&self) -> usize {
+        self.wasted_to_alignment.load(Ordering::Relaxed)
+    }
 
-            // make sure memory is aligned
-            let start = offset + align_offset;
-            // end will always be aligned if start is aligned since the requested size can only be multiples
-            let end = start + requested_size;
+    /// returns the amount of allocations that were handed out and not deallocated yet.
+    pub fn live_allocations(&self) -> usize {
+        self.live_allocations.load(Ordering::Relaxed)
+    }
 
-            if end > self.capacity() {
-                return Err(AllocError);
-            }
+    /// returns the ratio of alignment padding to used space.
+    /// a high ratio means that allocations should be reordered by alignment to reduce padding.
+    pub fn fragmentation(&self) -> f64 {
+        let used = self.used_space();
 
-            // if there is enough space available and nobody else claimed that space in the meantime the result is returned.
-            // otherwise the process needs to be retried.
-            if self
-                .offset
-                .compare_exchange(offset, end, Ordering::Release, Ordering::SeqCst)
-                .is_ok()
-            {
-                return Ok((start, end));
-            }
+        if used == 0 {
+            return 0.0;
         }
+
+        self.wasted_to_alignment() as f64 / used as f64
     }
 
-    /// SAFETY: must not be called while any &mut to the memory pool exist.
-    /// this means ALL allocations were freed beforehand.
-    #[cfg(test)]
-    pub unsafe fn print(&self) {
-        unsafe {
-            println!("{:?}", self.mem_pool.as_ref());
+    /// returns a snapshot of the allocation statistics since the arena was created or reset.
+    pub fn stats(&self) -> ArenaStats {
+        let stats = &self.stats;
+        ArenaStats {
+            allocations: stats.allocations.load(Ordering::Relaxed),
+            failed_allocations: stats.failed_allocations.load(Ordering::Relaxed),
+            requested_bytes: stats.requested_bytes.load(Ordering::Relaxed),
+            consumed_bytes: stats.consumed_bytes.load(Ordering::Relaxed),
+            peak_offset: stats.peak_offset.load(Ordering::Relaxed).max(self.offset.load(Ordering::Relaxed)),
+            leaked_allocations: self.leaked_allocations.load(Ordering::Relaxed),
         }
     }
-}
 
-impl Drop for Arena {
-    fn drop(&mut self) {
-        unsafe {
-            let _: Box<_> = Box::from_raw(self.mem_pool.as_mut());
+    /// applies the `LeakPolicy` if there are outstanding allocations.
+    /// returns the amount of outstanding allocations if the policy asks for a panic, which the caller raises with
+    /// `leak_panic` once the arena is in a consistent state again.
+    fn check_leaks(&self) -> Option<usize> {
+        let live = self.live_allocations();
+        if live == 0 {
+            return None;
+        }
+
+        match self.leak_policy {
+            LeakPolicy::Ignore => {}
+            LeakPolicy::Report => {
+                self.leaked_allocations.fetch_add(live, Ordering::Relaxed);
+            }
+            #[cfg(feature = "std")]
+            LeakPolicy::Warn => std::eprintln!("xs_arena_allocator: {live} allocations are still outstanding"),
+            LeakPolicy::DebugPanic => {
+                // a second panic while unwinding would abort the process.
+                #[cfg(feature = "std")]
+                if std::thread::panicking() {
+                    return None;
+                }
+                if cfg!(debug_assertions) {
+                    return Some(live);
+                }
+            }
         }
+        None
     }
-}
 
-/// bump style arena allocator.
-#[derive(Clone)]
-pub struct ArenaAllocator {
-    arena: Arc<Arena>,
-}
+    /// has to be called before the offset moves backwards, so the peak isn't lost.
+    fn record_peak(&self) {
+        self.stats.peak_offset.fetch_max(self.offset.load(Ordering::Relaxed), Ordering::Relaxed);
+    }
 
-impl ArenaAllocator {
-    pub fn new(arena: Arena) -> Self {
-        Self { arena: Arc::new(arena) }
+    /// returns a pointer to a memory slice with the alignment of the passed `Layout`.
+    /// the slice is at least as large as the layout and may be larger depending on the `RoundingPolicy`.
+    #[cfg_attr(feature = "profile", track_caller)]
+    pub fn get_next_mem_slice(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.allocate_with_offset(layout).map(|(slice, _)| slice)
     }
 
-    /// SAFETY: the arena must not be used while there are still active allocations.
-    #[cfg(test)]
-    pub unsafe fn get_arena(&self) -> &Arena {
-        &self.arena
+    /// same as `get_next_mem_slice` but zeroes the returned slice.
+    /// only the handed out memory is zeroed, the rest of the memory pool is left untouched.
+    #[cfg_attr(feature = "profile", track_caller)]
+    pub fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        let slice = self.get_next_mem_slice(layout)?;
+        unsafe { slice.as_mut_ptr().write_bytes(0, slice.len()) };
+        Ok(slice)
     }
-}
 
-unsafe impl Allocator for ArenaAllocator {
-    fn allocate(&self, layout: Layout) -> Result<std::ptr::NonNull<[u8]>, AllocError> {
-        self.arena.get_next_mem_slice(layout)
+    /// same as `get_next_mem_slice` but additionally returns the offset of the slice relative to the start of the memory pool.
+    /// the offset stays valid when the contents of the memory pool are relocated, unlike the pointer.
+    #[cfg_attr(feature = "profile", track_caller)]
+    pub fn allocate_with_offset(&self, layout: Layout) -> Result<(NonNull<[u8]>, usize), AllocError> {
+        Ok(self.try_allocate_with_offset(layout)?)
     }
 
-    unsafe fn deallocate(&self, _ptr: std::ptr::NonNull<u8>, _layout: Layout) {}
-}
+    /// same as `get_next_mem_slice` but describes why the allocation failed.
+    #[cfg_attr(feature = "profile", track_caller)]
+    pub fn try_allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, ArenaError> {
+        self.try_allocate_with_offset(layout).map(|(slice, _)| slice)
+    }
 
-#[cfg(test)]
-mod test {
-    use std::thread::{self, JoinHandle};
+    /// same as `allocate_with_offset` but describes why the allocation failed.
+    #[cfg_attr(feature = "profile", track_caller)]
+    pub fn try_allocate_with_offset(&self, layout: Layout) -> Result<(NonNull<[u8]>, usize), ArenaError> {
+        let (start, end, _) = self.allocate_blocks(layout, 1)?;
+        unsafe { Ok((self.mem_pool.get_unchecked_mut(start..end), start)) }
+    }
 
-    use super::*;
+    /// reserves `count` blocks for `layout` with a single bump of the offset, each of them counts as an allocation.
+    /// returns the offsets of the start of the first and the end of the last block, and the distance between the starts
+    /// of two blocks, which is the rounded size padded to the alignment. `count` must not be 0.
+    #[cfg_attr(feature = "profile", track_caller)]
+    pub(crate) fn allocate_blocks(&self, layout: Layout, count: usize) -> Result<(usize, usize, usize), ArenaError> {
+        // the allocation has to be counted before the offset is bumped,
+        // otherwise a concurrent `release` could see no live allocations and rewind the offset underneath it.
+        self.live_allocations.fetch_add(count, Ordering::SeqCst);
 
-    #[test]
-    fn allocation() {
-        let thread_count = 100;
-        // 2x1 byte + 1x4 byte allocation that can take up to 7 bytes with alignment = 9 bytes max per thread
-        let arena = Arena::new(thread_count * 9);
-        let arena_alloc = ArenaAllocator::new(arena);
-        let mut join_handles = Vec::with_capacity(thread_count);
+        let bounds = self.rounded_layout(layout).and_then(|rounded| {
+            let stride = rounded.pad_to_align().size();
+            let total = stride
+                .checked_mul(count - 1)
+                .and_then(|size| size.checked_add(rounded.size()))
+                .ok_or(ArenaErrorKind::Overflow)?;
+            let total = Layout::from_size_align(total, rounded.align()).map_err(|_| ArenaErrorKind::Overflow)?;
+            self.get_aligned_memory_bounds(total).map(|bounds| (bounds, stride))
+        });
+        let ((start, end), stride) = match bounds {
+            Ok(bounds) => bounds,
+            Err(kind) => {
+                self.live_allocations.fetch_sub(count, Ordering::SeqCst);
+                self.last_error.store(kind.to_u8(), Ordering::Relaxed);
+                self.stats.failed_allocations.fetch_add(1, Ordering::Relaxed);
+                return Err(ArenaError {
+                    layout,
+                    available: self.available_space(),
+                    kind,
+                });
+            }
+        };
 
-        for _ in 0..thread_count {
-            let alloc = arena_alloc.clone();
-            join_handles.push(spawn_allocating_thread(alloc));
+        if self.last_error.load(Ordering::Relaxed) != 0 {
+            self.last_error.store(0, Ordering::Relaxed);
         }
+        self.stats.allocations.fetch_add(count, Ordering::Relaxed);
+        self.stats.requested_bytes.fetch_add(layout.size() * count, Ordering::Relaxed);
 
-        join_handles.into_iter().for_each(|j| {
-            j.join().unwrap();
-        });
+        #[cfg(feature = "asan")]
+        asan::unpoison(unsafe { self.mem_pool.as_mut_ptr().add(start) }, end - start);
 
+        #[cfg(feature = "debug-fill")]
         unsafe {
-            arena_alloc.get_arena().print();
-            println!("available bytes: {}", arena_alloc.arena.available_space())
+            self.mem_pool.as_mut_ptr().add(start).write_bytes(DEBUG_FILL_ALLOCATED, end - start);
+        }
+
+        #[cfg(feature = "track-allocations")]
+        {
+            let size = end - start - stride * (count - 1);
+            self.allocations.lock().extend((0..count).map(|index| AllocationInfo {
+                offset: start + index * stride,
+                size,
+                align: layout.align(),
+            }));
+        }
+
+        #[cfg(feature = "track-threads")]
+        {
+            *self.allocations_by_thread.lock().entry(thread::current().id()).or_default() += count;
         }
+
+        #[cfg(feature = "profile")]
+        self.record_call_site(core::panic::Location::caller(), count, end - start);
+
+        Ok((start, end, stride))
     }
 
-    fn spawn_allocating_thread(arena_allocator: ArenaAllocator) -> JoinHandle<()> {
-        thread::spawn(move || {
-            let mut vec1 = Vec::<u8, ArenaAllocator>::with_capacity_in(2, arena_allocator.clone());
-            let mut vec2 = Vec::<u32, ArenaAllocator>::with_capacity_in(1, arena_allocator);
-            vec1.push(0xAA);
-            vec1.push(0x55);
-            vec2.push(0xFFFFFFFF);
-        })
+    /// returns the reason of the last failed allocation, or `None` if the last allocation succeeded.
+    pub fn last_error_kind(&self) -> Option<ArenaErrorKind> {
+        ArenaErrorKind::from_u8(self.last_error.load(Ordering::Relaxed))
+    }
+
+    /// returns the metadata of all allocations that were not deallocated yet, in allocation order.
+    #[cfg(feature = "track-allocations")]
+    pub fn iter_allocations(&self) -> impl Iterator<Item = AllocationInfo> {
+        self.allocations.lock().clone().into_iter()
+    }
+
+    /// returns the amount of successful allocations made by every thread since the arena was created or reset.
+    #[cfg(feature = "track-threads")]
+    pub fn allocations_by_thread(&self) -> HashMap<ThreadId, usize> {
+        self.allocations_by_thread.lock().clone()
+    }
+
+    /// hands the block of `ptr` back to the arena. if it is the most recent allocation the offset is rolled back,
+    /// so its space can be reused right away, which keeps stack-like allocate/free patterns from exhausting the pool.
+    /// returns true if the space was reclaimed.
+    ///
+    /// # Safety
+    /// `ptr` must have been allocated from this arena with `layout` and must not be used afterwards.
+    pub unsafe fn free_last(&self, ptr: NonNull<u8>, layout: Layout) -> bool {
+        // the block still belongs to the caller until the offset is rolled back, so it can't be handed out concurrently.
+        #[cfg(feature = "asan")]
+        asan::poison(ptr.as_ptr(), self.rounded_layout(layout).map_or(layout.size(), |layout| layout.size()));
+
+        let reclaimed = self.rewind_last(ptr, layout);
+        self.release(ptr);
+        reclaimed
+    }
+
+    /// moves the offset back to the start of the block of `ptr` if the block ends at the current offset.
+    fn rewind_last(&self, ptr: NonNull<u8>, layout: Layout) -> bool {
+        let Ok(reserved) = self.rounded_layout(layout) else {
+            return false;
+        };
+        let start = self.offset_of(ptr);
+        self.record_peak();
+
+        start >= self.frozen.load(Ordering::SeqCst)
+            && self
+                .offset
+                .compare_exchange(start + reserved.size(), start, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok()
+    }
+
+    /// called for every block that is handed back to the arena.
+    /// once the last live allocation is released the whole memory pool is reclaimed.
+    fn release(&self, ptr: NonNull<u8>) {
+        #[cfg(feature = "track-allocations")]
+        {
+            let offset = self.offset_of(ptr);
+            self.allocations.lock().retain(|allocation| allocation.offset != offset);
+        }
+        #[cfg(not(feature = "track-allocations"))]
+        let _ = ptr;
+
+        if self.live_allocations.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.reclaim();
+        }
+    }
+
+    /// rewinds the offset to the frozen prefix if there are no live allocations.
+    fn reclaim(&self) {
+        let offset = self.offset.load(Ordering::SeqCst);
+        let back = self.back.load(Ordering::SeqCst);
+        let frozen = self.frozen.load(Ordering::SeqCst);
+
+        // another thread might have started an allocation in the meantime.
+        // if it already bumped the offset the compare_exchange fails, otherwise it will retry from the rewound offset.
+        if self.live_allocations.load(Ordering::SeqCst) != 0 {
+            return;
+        }
+
+        if back != self.capacity() {
+            let mut back_ends = self.back_ends.lock();
+            if self
+                .back
+                .compare_exchange(back, self.capacity(), Ordering::SeqCst, Ordering::Relaxed)
+                .is_ok()
+            {
+                back_ends.clear();
+            }
+        }
+        if offset <= frozen {
+            return;
+        }
+
+        self.record_peak();
+        if self
+            .offset
+            .compare_exchange(offset, frozen, Ordering::SeqCst, Ordering::Relaxed)
+            .is_ok()
+            && frozen == 0
+        {
+            // padding of allocations that raced with the reclaim is lost, which only skews the statistic.
+            self.wasted_to_alignment.store(0, Ordering::Relaxed);
+        }
+    }
+
+    /// returns whether the block of `ptr` with the passed layout lies in the memory pool.
+    /// zero-size blocks can start right at the end of the pool.
+    #[cfg(feature = "std")]
+    fn contains(&self, ptr: NonNull<u8>, layout: Layout) -> bool {
+        let start = self.mem_pool.as_mut_ptr().addr();
+        let end = start + self.capacity();
+        match layout.size() {
+            0 => (start..=end).contains(&ptr.as_ptr().addr()),
+            _ => (start..end).contains(&ptr.as_ptr().addr()),
+        }
+    }
+
+    /// returns the offset of `ptr` relative to the start of the memory pool.
+    pub(crate) fn offset_of(&self, ptr: NonNull<u8>) -> usize {
+        ptr.as_ptr().addr() - self.mem_pool.as_mut_ptr().addr()
+    }
+
+    /// returns the layout that actually gets reserved for the passed layout.
+    fn rounded_layout(&self, layout: Layout) -> Result<Layout, ArenaErrorKind> {
+        let size = self.rounding.round(layout.size()).ok_or(ArenaErrorKind::Overflow)?;
+        Layout::from_size_align(size, layout.align()).map_err(|_| ArenaErrorKind::Overflow)
+    }
+
+    /// returns the block of `ptr` if the space reserved for `old_layout` is already large enough for `new_layout`.
+    fn grow_in_place(&self, ptr: NonNull<u8>, old_layout: Layout, new_layout: Layout) -> Option<NonNull<[u8]>> {
+        let reserved = self.rounded_layout(old_layout).ok()?.size();
+
+        if new_layout.size() > reserved || ptr.as_ptr().align_offset(new_layout.align()) != 0 {
+            return None;
+        }
+
+        Some(NonNull::slice_from_raw_parts(ptr, reserved))
+    }
+
+    /// grows or shrinks the block of `ptr` by moving the offset if the block ends at the current offset.
+    /// returns `None` if another allocation was made after it in the meantime or the new size doesn't fit.
+    fn resize_last(&self, ptr: NonNull<u8>, old_layout: Layout, new_layout: Layout) -> Option<NonNull<[u8]>> {
+        if ptr.as_ptr().align_offset(new_layout.align()) != 0 {
+            return None;
+        }
+
+        let start = self.offset_of(ptr);
+        let old_end = start + self.rounded_layout(old_layout).ok()?.size();
+        let new_end = start.checked_add(self.rounded_layout(new_layout).ok()?.size())?;
+
+        // memory below a mark must stay allocated, otherwise the mark would point past the offset.
+        if new_end > self.back.load(Ordering::SeqCst) || new_end < self.frozen.load(Ordering::SeqCst) {
+            return None;
+        }
+
+        #[cfg(any(all(any(unix, windows), feature = "std"), target_arch = "wasm32"))]
+        self.ensure_committed(new_end).ok()?;
+
+        self.record_peak();
+        self.offset
+            .compare_exchange(old_end, new_end, Ordering::SeqCst, Ordering::SeqCst)
+            .ok()?;
+        if !self.front_fits(new_end) {
+            let _ = self
+                .offset
+                .compare_exchange(new_end, old_end, Ordering::SeqCst, Ordering::Relaxed);
+            return None;
+        }
+        self.stats.consumed_bytes.fetch_add(new_end.saturating_sub(old_end), Ordering::Relaxed);
+
+        #[cfg(feature = "asan")]
+        asan::unpoison(ptr.as_ptr(), new_end - start);
+
+        #[cfg(feature = "track-allocations")]
+        if let Some(allocation) = self.allocations.lock().iter_mut().find(|allocation| allocation.offset == start) {
+            allocation.size = new_end - start;
+        }
+
+        unsafe { Some(self.mem_pool.get_unchecked_mut(start..new_end)) }
+    }
+
+    /// returns the offset start and end for the allocated memory.
+    fn get_aligned_memory_bounds(&self, layout: Layout) -> Result<(usize, usize), ArenaErrorKind> {
+        let requested_size = layout.size();
+
+        loop {
+            // get a copy of the current value of the offset.
+            // it is important that the value used for the calculations can't be changed by another thread
+            // since we need to very the result with a compare_exchange in the end.
+            let offset = self.offset.load(Ordering::Acquire);
+            let align_offset = unsafe { self.mem_pool.as_mut_ptr().add(offset).align_offset(layout.align()) };
+
+            // failed to find suitable alignment
+            if align_offset == usize::MAX {
+                return Err(ArenaErrorKind::AlignmentUnsatisfiable);
+            }
+
+            // make sure memory is aligned
+            let start = offset.checked_add(align_offset).ok_or(ArenaErrorKind::Overflow)?;
+            // end will always be aligned if start is aligned since the requested size can only be multiples
+            let end = start.checked_add(requested_size).ok_or(ArenaErrorKind::Overflow)?;
+
+            if end > self.back.load(Ordering::SeqCst) {
+                return Err(self.exhaustion_kind(layout));
+            }
+
+            #[cfg(any(all(any(unix, windows), feature = "std"), target_arch = "wasm32"))]
+            self.ensure_committed(end)?;
+
+            // if there is enough space available and nobody else claimed that space in the meantime the result is returned.
+            // otherwise the process needs to be retried.
+            if self
+                .offset
+                .compare_exchange(offset, end, Ordering::SeqCst, Ordering::SeqCst)
+                .is_ok()
+            {
+                if !self.front_fits(end) {
+                    // the space is lost if another allocation was made after it in the meantime.
+                    let _ = self
+                        .offset
+                        .compare_exchange(end, offset, Ordering::SeqCst, Ordering::Relaxed);
+                    return Err(ArenaErrorKind::OutOfSpace);
+                }
+
+                self.wasted_to_alignment.fetch_add(align_offset, Ordering::Relaxed);
+                self.stats.consumed_bytes.fetch_add(end - offset, Ordering::Relaxed);
+                return Ok((start, end));
+            }
+        }
+    }
+
+    /// compares the used contents of two arenas.
+    /// returns false if the arenas used a different amount of bytes or if any of the used bytes differ.
+    ///
+    /// # Safety
+    /// must not be called while any &mut to the memory pool of either arena exist.
+    /// this means ALL allocations of both arenas were freed beforehand or are no longer written to.
+    /// for arenas created from uninitialized memory every used byte must have been written.
+    pub unsafe fn content_eq(&self, other: &Arena) -> bool {
+        let used = self.offset.load(Ordering::Acquire);
+
+        if used != other.offset.load(Ordering::Acquire) {
+            return false;
+        }
+
+        // the padding between allocations is poisoned but is compared as well.
+        #[cfg(feature = "asan")]
+        {
+            asan::unpoison(self.mem_pool.as_mut_ptr(), used);
+            asan::unpoison(other.mem_pool.as_mut_ptr(), used);
+        }
+
+        unsafe { self.mem_pool.as_ref()[..used] == other.mem_pool.as_ref()[..used] }
+    }
+
+    /// commits the memory pool up to at least `end` if the arena commits in steps.
+    #[cfg(any(all(any(unix, windows), feature = "std"), target_arch = "wasm32"))]
+    fn ensure_committed(&self, end: usize) -> Result<(), ArenaErrorKind> {
+        match &self.backing {
+            #[cfg(all(any(unix, windows), feature = "std"))]
+            Backing::Mmap { commit: Some(commit), .. } => self.commit_mapped(commit, end),
+            #[cfg(target_arch = "wasm32")]
+            Backing::Wasm { committed } => self.grow_linear_memory(committed, end),
+            _ => Ok(()),
+        }
+    }
+
+    #[cfg(all(any(unix, windows), feature = "std"))]
+    fn commit_mapped(&self, commit: &Commit, end: usize) -> Result<(), ArenaErrorKind> {
+        let committed = commit.committed.load(Ordering::Acquire);
+        if end <= committed {
+            return Ok(());
+        }
+
+        // concurrent calls may commit overlapping ranges, which is harmless.
+        let target = end.next_multiple_of(commit.step).min(self.capacity());
+        unsafe { sys::commit(self.mem_pool.as_mut_ptr().add(committed), target - committed) }
+            .map_err(|_| ArenaErrorKind::OutOfSpace)?;
+        commit.committed.fetch_max(target, Ordering::Release);
+        Ok(())
+    }
+
+    /// distinguishes a full arena from an alignment that can't be satisfied anywhere in the memory pool.
+    fn exhaustion_kind(&self, layout: Layout) -> ArenaErrorKind {
+        let base_align_offset = self.mem_pool.as_mut_ptr().align_offset(layout.align());
+
+        if base_align_offset > self.capacity() {
+            ArenaErrorKind::AlignmentUnsatisfiable
+        } else {
+            ArenaErrorKind::OutOfSpace
+        }
+    }
+
+    /// overwrites every part of the memory pool that may have been handed out with `DEBUG_FILL_FREED`.
+    /// lazily committed mmap-backed pools are only filled up to the highest offset, to not commit untouched pages.
+    #[cfg(feature = "debug-fill")]
+    fn debug_fill_pool(&mut self) {
+        #[cfg(feature = "asan")]
+        self.unpoison_pool();
+
+        let front = match &self.backing {
+            #[cfg(all(any(unix, windows), feature = "std"))]
+            Backing::Mmap { commit: None, .. } => self.stats().peak_offset,
+            _ => self.committed(),
+        };
+        let back = *self.back.get_mut();
+
+        unsafe {
+            let base = self.mem_pool.as_mut_ptr();
+            base.write_bytes(DEBUG_FILL_FREED, front.min(back));
+            base.add(back).write_bytes(DEBUG_FILL_FREED, self.capacity() - back);
+        }
+    }
+
+    /// # Safety
+    /// must not be called while any &mut to the memory pool exist.
+    /// this means ALL allocations were freed beforehand.
+    #[cfg(test)]
+    pub unsafe fn print(&self) {
+        unsafe {
+            std::println!("{:?}", self.mem_pool.as_ref());
+        }
+    }
+}
+
+impl Drop for Arena {
+    fn drop(&mut self) {
+        let leaked = self.check_leaks();
+        self.run_drops(0);
+        #[cfg(feature = "asan")]
+        self.unpoison_pool();
+        // memory of somebody else may still be read by its owner after the arena is gone.
+        #[cfg(feature = "debug-fill")]
+        if !matches!(self.backing, Backing::External) {
+            self.debug_fill_pool();
+        }
+
+        match self.backing {
+            Backing::Boxed => unsafe {
+                let _: Box<[MaybeUninit<u8>]> = Box::from_raw(self.mem_pool.as_ptr() as *mut [MaybeUninit<u8>]);
+            },
+            Backing::Aligned(layout) if layout.size() > 0 => unsafe {
+                alloc::alloc::dealloc(self.mem_pool.as_mut_ptr(), layout);
+            },
+            Backing::Aligned(_) | Backing::External => {}
+            #[cfg(target_arch = "wasm32")]
+            Backing::Wasm { .. } => {}
+            #[cfg(all(any(unix, windows), feature = "std"))]
+            Backing::Mmap { guard, .. } => unsafe {
+                sys::release(self.mem_pool.as_mut_ptr().sub(guard), self.capacity() + 2 * guard);
+            },
+        }
+
+        if let Some(live) = leaked {
+            leak_panic(live);
+        }
+    }
+}
+
+/// panics for `LeakPolicy::DebugPanic`.
+#[cold]
+fn leak_panic(live: usize) -> ! {
+    panic!("{live} allocations are still outstanding");
+}
+
+/// bump style arena allocator.
+#[derive(Clone)]
+pub struct ArenaAllocator {
+    arena: Arc<Arena>,
+    hooks: Option<Arc<dyn ArenaHooks>>,
+    #[cfg(feature = "metrics")]
+    metrics: Option<Arc<ArenaMetrics>>,
+}
+
+impl ArenaAllocator {
+    pub fn new(arena: Arena) -> Self {
+        Self {
+            arena: Arc::new(arena),
+            hooks: None,
+            #[cfg(feature = "metrics")]
+            metrics: None,
+        }
+    }
+
+    /// returns the reason of the last failed allocation, or `None` if the last allocation succeeded.
+    pub fn last_error_kind(&self) -> Option<ArenaErrorKind> {
+        self.arena.last_error_kind()
+    }
+
+    /// returns a snapshot of the allocation statistics of the arena.
+    pub fn stats(&self) -> ArenaStats {
+        self.arena.stats()
+    }
+
+    /// resets the arena if this is the only handle to it and every allocation was deallocated.
+    /// returns whether the arena was reset.
+    pub fn try_reset(&mut self) -> bool {
+        match Arc::get_mut(&mut self.arena) {
+            Some(arena) if arena.live_allocations() == 0 => {
+                arena.reset();
+                self.notify_reset();
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// copies `values` into the arena. see `Arena::alloc_slice_copy`.
+    #[allow(clippy::mut_from_ref)]
+    #[cfg_attr(feature = "profile", track_caller)]
+    pub fn alloc_slice_copy<T: Copy>(&self, values: &[T]) -> &mut [T] {
+        self.arena.alloc_slice_copy(values)
+    }
+
+    /// allocates a slice of `len` values created by `f`. see `Arena::alloc_slice_fill_with`.
+    #[allow(clippy::mut_from_ref)]
+    #[cfg_attr(feature = "profile", track_caller)]
+    pub fn alloc_slice_fill_with<T>(&self, len: usize, f: impl FnMut(usize) -> T) -> &mut [T] {
+        self.arena.alloc_slice_fill_with(len, f)
+    }
+
+    /// copies `string` into the arena. see `Arena::alloc_str`.
+    #[allow(clippy::mut_from_ref)]
+    #[cfg_attr(feature = "profile", track_caller)]
+    pub fn alloc_str(&self, string: &str) -> &mut str {
+        self.arena.alloc_str(string)
+    }
+
+    /// moves the items of `iter` into a slice in the arena. see `Arena::alloc_from_iter`.
+    #[allow(clippy::mut_from_ref)]
+    #[cfg_attr(feature = "profile", track_caller)]
+    pub fn alloc_from_iter<T, I>(&self, iter: I) -> &mut [T]
+    where
+        I: IntoIterator<Item = T>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        self.arena.alloc_from_iter(iter)
+    }
+
+    /// # Safety
+    /// the arena must not be used while there are still active allocations.
+    #[cfg(test)]
+    pub unsafe fn get_arena(&self) -> &Arena {
+        &self.arena
+    }
+}
+
+impl From<Arc<Arena>> for ArenaAllocator {
+    /// wraps an arena that is already shared, `Arc<Arena>` can't implement `Allocator` itself.
+    fn from(arena: Arc<Arena>) -> Self {
+        Self {
+            arena,
+            hooks: None,
+            #[cfg(feature = "metrics")]
+            metrics: None,
+        }
+    }
+}
+
+impl Arena {
+    /// grows the block of `ptr` in place if possible and moves it to a block returned by `allocate` otherwise.
+    unsafe fn grow_with(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+        allocate: impl FnOnce(Layout) -> Result<NonNull<[u8]>, AllocError>,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        let in_place = self
+            .grow_in_place(ptr, old_layout, new_layout)
+            .or_else(|| self.resize_last(ptr, old_layout, new_layout));
+        if let Some(block) = in_place {
+            return Ok(block);
+        }
+
+        let new_block = allocate(new_layout)?;
+        unsafe {
+            ptr::copy_nonoverlapping(ptr.as_ptr(), new_block.as_mut_ptr(), old_layout.size());
+            self.free_last(ptr, old_layout);
+        }
+        Ok(new_block)
+    }
+
+    /// shrinks the block of `ptr` in place if possible and moves it to a block returned by `allocate` otherwise.
+    unsafe fn shrink_with(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+        allocate: impl FnOnce(Layout) -> Result<NonNull<[u8]>, AllocError>,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        if let Some(block) = self.resize_last(ptr, old_layout, new_layout) {
+            return Ok(block);
+        }
+
+        // the block stays where it is, only the space after it can't be reused.
+        if ptr.as_ptr().align_offset(new_layout.align()) == 0 {
+            return Ok(NonNull::slice_from_raw_parts(ptr, new_layout.size()));
+        }
+
+        let new_block = allocate(new_layout)?;
+        unsafe {
+            ptr::copy_nonoverlapping(ptr.as_ptr(), new_block.as_mut_ptr(), new_layout.size());
+            self.free_last(ptr, old_layout);
+        }
+        Ok(new_block)
+    }
+}
+
+/// zeroes the part of a grown block after the first `old_size` bytes.
+fn zero_tail(block: NonNull<[u8]>, old_size: usize) -> NonNull<[u8]> {
+    unsafe { block.as_mut_ptr().add(old_size).write_bytes(0, block.len() - old_size) };
+    block
+}
+
+/// allows allocating from a borrowed arena, e.g. `Vec::new_in(&arena)`, without the reference count of an
+/// `ArenaAllocator`. the borrow keeps the arena from being reset or dropped while collections use it.
+unsafe impl Allocator for Arena {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.get_next_mem_slice(layout)
+    }
+
+    fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        Arena::allocate_zeroed(self, layout)
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        unsafe { self.free_last(ptr, layout) };
+    }
+
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        unsafe { self.grow_with(ptr, old_layout, new_layout, |layout| self.get_next_mem_slice(layout)) }
+    }
+
+    unsafe fn grow_zeroed(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        Ok(zero_tail(unsafe { self.grow(ptr, old_layout, new_layout)? }, old_layout.size()))
+    }
+
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        unsafe { self.shrink_with(ptr, old_layout, new_layout, |layout| self.get_next_mem_slice(layout)) }
+    }
+}
+
+unsafe impl Allocator for ArenaAllocator {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.notify_alloc(layout, self.arena.get_next_mem_slice(layout))
+    }
+
+    fn allocate_zeroed(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.notify_alloc(layout, self.arena.allocate_zeroed(layout))
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        unsafe { self.arena.free_last(ptr, layout) };
+    }
+
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        unsafe { self.arena.grow_with(ptr, old_layout, new_layout, |layout| self.allocate(layout)) }
+    }
+
+    unsafe fn grow_zeroed(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        Ok(zero_tail(unsafe { self.grow(ptr, old_layout, new_layout)? }, old_layout.size()))
+    }
+
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        unsafe { self.arena.shrink_with(ptr, old_layout, new_layout, |layout| self.allocate(layout)) }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread::{self, JoinHandle};
+
+    use super::*;
+
+    #[test]
+    fn allocation() {
+        let thread_count = 100;
+        // 2x1 byte + 1x4 byte allocation that can take up to 7 bytes with alignment = 9 bytes max per thread
+        // the padding between the allocations is printed as well, so it has to be initialized.
+        let arena = Arena::builder().capacity(thread_count * 9).zeroed(true).build().unwrap().into_fixed().unwrap();
+        let arena_alloc = ArenaAllocator::new(arena);
+        let mut join_handles = Vec::with_capacity(thread_count);
+
+        for _ in 0..thread_count {
+            let alloc = arena_alloc.clone();
+            join_handles.push(spawn_allocating_thread(alloc));
+        }
+
+        join_handles.into_iter().for_each(|j| {
+            j.join().unwrap();
+        });
+
+        unsafe {
+            arena_alloc.get_arena().print();
+            println!("available bytes: {}", arena_alloc.arena.available_space())
+        }
+    }
+
+    #[test]
+    fn content_eq() {
+        let arena1 = Arena::new(64);
+        let arena2 = Arena::new(64);
+        let arena3 = Arena::new(64);
+
+        write_bytes(&arena1, &[1, 2, 3, 4]);
+        write_bytes(&arena1, &[5, 6]);
+        write_bytes(&arena2, &[1, 2, 3, 4]);
+        write_bytes(&arena2, &[5, 6]);
+        write_bytes(&arena3, &[1, 2, 3, 4]);
+        write_bytes(&arena3, &[5, 7]);
+
+        unsafe {
+            assert!(arena1.content_eq(&arena2));
+            assert!(!arena1.content_eq(&arena3));
+        }
+
+        // same bytes so far but a different amount of used space
+        write_bytes(&arena2, &[8]);
+        unsafe {
+            assert!(!arena1.content_eq(&arena2));
+        }
+    }
+
+    #[test]
+    fn rounding_reduces_reallocations() {
+        // grows 1 -> 8 -> 16 -> 32 -> 64 without rounding
+        assert_eq!(count_reallocations(RoundingPolicy::Off, 1, 64), 4);
+        assert_eq!(count_reallocations(RoundingPolicy::Quantum(64), 1, 64), 0);
+
+        // 5 bytes get rounded up to 8, so growing the capacity to 8 fits into the first block
+        for (rounding, moved) in [(RoundingPolicy::Off, true), (RoundingPolicy::NextPowerOfTwo, false)] {
+            let arena_alloc = ArenaAllocator::new(Arena::new(64).with_rounding(rounding));
+            let mut vec = Vec::<u8, ArenaAllocator>::with_capacity_in(5, arena_alloc.clone());
+            let _blocker = arena_alloc.allocate(Layout::new::<u8>()).unwrap();
+            let ptr = vec.as_ptr();
+            vec.reserve_exact(8);
+            assert_eq!(vec.as_ptr() != ptr, moved);
+        }
+    }
+
+    #[test]
+    fn rounding_returns_whole_block() {
+        let arena = Arena::new(256).with_rounding(RoundingPolicy::Quantum(16));
+        let slice = arena.get_next_mem_slice(Layout::new::<[u8; 3]>()).unwrap();
+        assert_eq!(slice.len(), 16);
+        assert_eq!(arena.available_space(), 240);
+
+        let arena = Arena::new(256).with_rounding(RoundingPolicy::Minimum(32));
+        assert_eq!(arena.get_next_mem_slice(Layout::new::<u8>()).unwrap().len(), 32);
+        assert_eq!(arena.get_next_mem_slice(Layout::new::<[u8; 33]>()).unwrap().len(), 33);
+
+        // the padding becomes capacity that collections grow into in place
+        let arena_alloc = ArenaAllocator::new(Arena::new(256).with_rounding(RoundingPolicy::Minimum(16)));
+        let mut vec = Vec::<u8, ArenaAllocator>::with_capacity_in(1, arena_alloc.clone());
+        let _blocker = arena_alloc.allocate(Layout::new::<u8>()).unwrap();
+        let ptr = vec.as_ptr();
+        vec.extend([1; 16]);
+        assert_eq!(vec.as_ptr(), ptr);
+
+        // sizes above the cap are left untouched
+        let arena = Arena::new(2 * MAX_ROUNDED_SIZE).with_rounding(RoundingPolicy::NextPowerOfTwo);
+        let slice = arena.get_next_mem_slice(Layout::array::<u8>(MAX_ROUNDED_SIZE + 1).unwrap()).unwrap();
+        assert_eq!(slice.len(), MAX_ROUNDED_SIZE + 1);
+    }
+
+    #[test]
+    fn resize_last_allocation_in_place() {
+        let arena_alloc = ArenaAllocator::new(Arena::new(64));
+        let arena = unsafe { arena_alloc.get_arena() };
+
+        let mut vec = Vec::<u8, ArenaAllocator>::with_capacity_in(4, arena_alloc.clone());
+        vec.extend([1, 2, 3, 4]);
+        let ptr = vec.as_ptr();
+        vec.reserve_exact(28);
+        assert_eq!(vec.as_ptr(), ptr);
+        assert_eq!(arena.used_space(), 32);
+
+        vec.shrink_to(8);
+        assert_eq!(vec.as_ptr(), ptr);
+        assert_eq!(arena.used_space(), 8);
+
+        // grown blocks are zeroed past the old size
+        let block = arena_alloc.allocate(Layout::new::<[u8; 8]>()).unwrap();
+        unsafe {
+            block.as_mut_ptr().write_bytes(0xFF, 8);
+            let grown = arena_alloc
+                .grow_zeroed(block.as_non_null_ptr(), Layout::new::<[u8; 8]>(), Layout::new::<[u8; 16]>())
+                .unwrap();
+            assert_eq!(grown.as_non_null_ptr(), block.as_non_null_ptr());
+            assert_eq!(grown.as_ref(), &[[0xFF; 8], [0; 8]].concat()[..]);
+        }
+
+        // blocks that aren't last are copied when growing
+        vec.reserve_exact(16);
+        assert_ne!(vec.as_ptr(), ptr);
+        assert_eq!(vec[..], [1, 2, 3, 4]);
+        assert_eq!(arena.used_space(), 8 + 16 + 20);
+    }
+
+    #[test]
+    fn lifo_deallocation() {
+        let arena = Arena::new(32);
+        let layout = Layout::new::<[u8; 8]>();
+        let first = arena.get_next_mem_slice(layout).unwrap().as_non_null_ptr();
+
+        // stack-like patterns never exhaust the pool
+        for _ in 0..100 {
+            let block = arena.get_next_mem_slice(layout).unwrap().as_non_null_ptr();
+            assert!(unsafe { arena.free_last(block, layout) });
+        }
+        assert_eq!(arena.used_space(), 8);
+
+        let second = arena.get_next_mem_slice(layout).unwrap().as_non_null_ptr();
+        unsafe {
+            // only the last allocation can be reclaimed
+            assert!(!arena.free_last(first, layout));
+            assert_eq!(arena.used_space(), 16);
+            assert!(arena.free_last(second, layout));
+        }
+        assert_eq!(arena.used_space(), 0);
+    }
+
+    #[test]
+    fn stats() {
+        let arena_alloc = ArenaAllocator::new(Arena::new_aligned(64, 4));
+        let vec1 = Vec::<u8, ArenaAllocator>::with_capacity_in(3, arena_alloc.clone());
+        let vec2 = Vec::<u32, ArenaAllocator>::with_capacity_in(2, arena_alloc.clone());
+        assert!(Vec::<u8, ArenaAllocator>::try_with_capacity_in(100, arena_alloc.clone()).is_err());
+        drop(vec2);
+        drop(vec1);
+
+        // the offset was reclaimed but the peak is kept
+        assert_eq!(
+            arena_alloc.stats(),
+            ArenaStats {
+                allocations: 2,
+                failed_allocations: 1,
+                requested_bytes: 11,
+                consumed_bytes: 12,
+                peak_offset: 12,
+                leaked_allocations: 0,
+            }
+        );
+
+        let mut arena = Arena::new(64);
+        arena.get_next_mem_slice(Layout::new::<u8>()).unwrap();
+        arena.reset();
+        assert_eq!(arena.stats(), ArenaStats::default());
+    }
+
+    #[test]
+    fn leak_policy() {
+        let mut arena = Arena::new(64).with_leak_policy(LeakPolicy::Report);
+        arena.get_next_mem_slice(Layout::new::<u8>()).unwrap();
+        arena.get_next_mem_slice(Layout::new::<u8>()).unwrap();
+        arena.reset();
+        arena.reset();
+        assert_eq!(arena.stats().leaked_allocations, 2);
+
+        let arena_alloc = ArenaAllocator::new(Arena::new(64).with_leak_policy(LeakPolicy::DebugPanic));
+        let mut vec = Vec::<u8, _>::with_capacity_in(8, arena_alloc.clone());
+        vec.push(1);
+        drop(vec);
+        drop(arena_alloc);
+    }
+
+    #[test]
+    #[should_panic = "1 allocations are still outstanding"]
+    fn leak_policy_panics() {
+        let mut arena = Arena::new(64).with_leak_policy(LeakPolicy::DebugPanic);
+        arena.get_next_mem_slice(Layout::new::<u8>()).unwrap();
+        arena.reset();
+    }
+
+    #[test]
+    fn leak_panics_after_the_reset() {
+        let mut arena = Arena::new(64).with_leak_policy(LeakPolicy::DebugPanic);
+        let handle = arena.insert(1u8);
+
+        // the arena is fully reset when the panic is caught
+        let reset = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| arena.reset()));
+        assert_eq!(reset.is_err(), cfg!(debug_assertions));
+        assert_eq!((arena.used_space(), arena.live_allocations()), (0, 0));
+        assert!(arena.get(handle).is_none());
+    }
+
+    #[test]
+    fn arena_error() {
+        let arena = Arena::new(16);
+        arena.get_next_mem_slice(Layout::new::<[u8; 10]>()).unwrap();
+
+        let layout = Layout::new::<u64>();
+        let err = arena.try_allocate(layout).unwrap_err();
+        assert_eq!(
+            err,
+            ArenaError {
+                layout,
+                available: 6,
+                kind: ArenaErrorKind::OutOfSpace,
+            }
+        );
+        assert_eq!(
+            err.to_string(),
+            "failed to allocate 8 bytes aligned to 8 with 6 bytes available: the arena is out of space"
+        );
+
+        let layout = Layout::from_size_align(1, 1 << 40).unwrap();
+        assert_eq!(arena.try_allocate(layout).unwrap_err().kind, ArenaErrorKind::AlignmentUnsatisfiable);
+    }
+
+    fn count_reallocations(rounding: RoundingPolicy, initial_capacity: usize, pushes: usize) -> usize {
+        let arena_alloc = ArenaAllocator::new(Arena::new(1024).with_rounding(rounding));
+        let mut vec = Vec::<u8, ArenaAllocator>::with_capacity_in(initial_capacity, arena_alloc.clone());
+        let mut ptr = vec.as_ptr();
+        let mut reallocations = 0;
+
+        // keeps the vec from being the last allocation, which would grow by moving the offset.
+        let mut blockers = vec![arena_alloc.allocate(Layout::new::<u8>()).unwrap()];
+        for i in 0..pushes {
+            vec.push(i as u8);
+            if vec.as_ptr() != ptr {
+                ptr = vec.as_ptr();
+                reallocations += 1;
+                blockers.push(arena_alloc.allocate(Layout::new::<u8>()).unwrap());
+            }
+        }
+
+        reallocations
+    }
+
+    #[cfg(feature = "track-allocations")]
+    #[test]
+    fn iter_allocations() {
+        let arena_alloc = ArenaAllocator::new(Arena::new(64));
+        let vec1 = Vec::<u8, ArenaAllocator>::with_capacity_in(3, arena_alloc.clone());
+        let vec2 = Vec::<u32, ArenaAllocator>::with_capacity_in(2, arena_alloc.clone());
+        let vec3 = Vec::<u16, ArenaAllocator>::with_capacity_in(5, arena_alloc.clone());
+        drop(vec2);
+
+        let allocations: Vec<_> = unsafe { arena_alloc.get_arena() }.iter_allocations().collect();
+        assert_eq!(allocations.len(), 2);
+        assert_eq!((allocations[0].offset, allocations[0].size, allocations[0].align), (0, 3, 1));
+        assert_eq!((allocations[1].size, allocations[1].align), (10, 2));
+        assert_eq!(allocations[1].offset, vec3.as_ptr().addr() - vec1.as_ptr().addr());
+    }
+
+    #[test]
+    fn uninit_memory_pool() {
+        let arena_alloc = ArenaAllocator::new(Arena::from_uninit_boxed_slice(Box::new_uninit_slice(64)));
+
+        let mut vec = Vec::<u32, ArenaAllocator>::with_capacity_in(4, arena_alloc.clone());
+        vec.extend([1, 2, 3, 4]);
+        assert_eq!(vec, [1, 2, 3, 4]);
+
+        let zeroed = arena_alloc.allocate_zeroed(Layout::new::<[u8; 16]>()).unwrap();
+        assert!(unsafe { zeroed.as_ref() }.iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn fragmentation() {
+        let arena = Arena::new(256);
+        assert_eq!(arena.fragmentation(), 0.0);
+
+        // every 1 byte allocation is followed by 7 bytes of padding for the next 8 byte allocation
+        for _ in 0..8 {
+            arena.get_next_mem_slice(Layout::new::<u8>()).unwrap();
+            arena.get_next_mem_slice(Layout::new::<u64>()).unwrap();
+        }
+
+        let fragmentation = arena.fragmentation();
+        assert!((0.4..=0.5).contains(&fragmentation), "fragmentation was {fragmentation}");
+
+        // allocations without padding only dilute the ratio
+        arena.get_next_mem_slice(Layout::new::<[u64; 16]>()).unwrap();
+        assert!(arena.fragmentation() < fragmentation);
+    }
+
+    #[test]
+    fn allocate_with_offset() {
+        let arena = Arena::new(64);
+        arena.get_next_mem_slice(Layout::new::<u8>()).unwrap();
+
+        let (slice, offset) = arena.allocate_with_offset(Layout::new::<u32>()).unwrap();
+        assert!(offset >= 1);
+        assert_eq!(unsafe { arena.mem_pool.as_mut_ptr().add(offset) }, slice.as_mut_ptr());
+        assert_eq!(arena.used_space(), offset + 4);
+    }
+
+    #[test]
+    fn try_reset() {
+        let mut arena_alloc = ArenaAllocator::new(Arena::new(64));
+        let vec = Vec::<u32, ArenaAllocator>::with_capacity_in(4, arena_alloc.clone());
+        assert!(!arena_alloc.try_reset());
+        drop(vec);
+
+        // a unique handle still refuses to reset while allocations are outstanding
+        let block1 = arena_alloc.allocate(Layout::new::<u64>()).unwrap();
+        let block2 = arena_alloc.allocate(Layout::new::<u64>()).unwrap();
+        unsafe { arena_alloc.deallocate(block1.as_non_null_ptr(), Layout::new::<u64>()) };
+        assert!(!arena_alloc.try_reset());
+
+        unsafe { arena_alloc.deallocate(block2.as_non_null_ptr(), Layout::new::<u64>()) };
+        assert!(arena_alloc.try_reset());
+        assert_eq!(unsafe { arena_alloc.get_arena() }.available_space(), 64);
+    }
+
+    #[test]
+    fn caller_provided_buffers() {
+        let arena = Arena::from_boxed_slice(vec![0xFF; 32].into_boxed_slice());
+        write_bytes(&arena, &[1; 32]);
+        assert!(arena.get_next_mem_slice(Layout::new::<u8>()).is_err());
+
+        static mut BUF: [u8; 32] = [0; 32];
+        let arena = Arena::from_static(unsafe { &mut *ptr::addr_of_mut!(BUF) });
+        assert_eq!(arena.capacity(), 32);
+        write_bytes(&arena, &[2; 16]);
+
+        let mut buf = [0u8; 16];
+        {
+            let arena = unsafe { Arena::from_raw_parts(buf.as_mut_ptr(), buf.len()) };
+            write_bytes(&arena, &[3; 8]);
+        }
+        assert_eq!(buf, [3, 3, 3, 3, 3, 3, 3, 3, 0, 0, 0, 0, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn sub_arena() {
+        let parent = Arena::new(256);
+        let block = parent.get_next_mem_slice(Layout::new::<[u8; 64]>()).unwrap();
+        let mut sub_arena = unsafe { Arena::from_external(block) };
+        assert_eq!(sub_arena.capacity(), 64);
+
+        for _ in 0..3 {
+            write_bytes(&sub_arena, &[0xAA; 40]);
+            assert!(sub_arena.get_next_mem_slice(Layout::new::<[u8; 40]>()).is_err());
+            sub_arena.reset();
+            assert_eq!(sub_arena.available_space(), 64);
+        }
+
+        drop(sub_arena);
+        assert_eq!(parent.used_space(), 64);
+    }
+
+    #[test]
+    fn last_error_kind() {
+        let arena_alloc = ArenaAllocator::new(Arena::new(16));
+        assert!(arena_alloc.allocate(Layout::new::<[u8; 32]>()).is_err());
+        assert_eq!(arena_alloc.last_error_kind(), Some(ArenaErrorKind::OutOfSpace));
+        assert!(arena_alloc.allocate(Layout::new::<[u8; 8]>()).is_ok());
+        assert_eq!(arena_alloc.last_error_kind(), None);
+
+        // a pool of 8 bytes starting 1 byte past a 16 byte boundary can't contain a 16 byte aligned address
+        let parent = Arena::new(64);
+        let block = parent.get_next_mem_slice(Layout::from_size_align(32, 16).unwrap()).unwrap();
+        let misaligned = unsafe { block.get_unchecked_mut(1..9) };
+        let arena_alloc = ArenaAllocator::new(unsafe { Arena::from_external(misaligned) });
+        assert!(arena_alloc.allocate(Layout::from_size_align(1, 16).unwrap()).is_err());
+        assert_eq!(arena_alloc.last_error_kind(), Some(ArenaErrorKind::AlignmentUnsatisfiable));
+
+        let arena_alloc = ArenaAllocator::new(Arena::new(16).with_rounding(RoundingPolicy::Quantum(usize::MAX / 2 + 1)));
+        assert!(arena_alloc.allocate(Layout::new::<u8>()).is_err());
+        assert_eq!(arena_alloc.last_error_kind(), Some(ArenaErrorKind::Overflow));
+    }
+
+    #[test]
+    fn reclaim_after_last_deallocation() {
+        let arena_alloc = ArenaAllocator::new(Arena::new(128));
+        let arena = unsafe { arena_alloc.get_arena() };
+
+        let mut vec1 = Vec::<u8, ArenaAllocator>::with_capacity_in(3, arena_alloc.clone());
+        let vec2 = Vec::<u64, ArenaAllocator>::with_capacity_in(2, arena_alloc.clone());
+        let vec3 = Vec::<u16, ArenaAllocator>::with_capacity_in(5, arena_alloc.clone());
+        vec1.extend([1, 2, 3, 4]);
+        assert_eq!(arena.live_allocations(), 3);
+
+        drop(vec1);
+        drop(vec3);
+        assert_eq!(arena.live_allocations(), 1);
+        assert!(arena.available_space() < 128);
+
+        drop(vec2);
+        assert_eq!(arena.live_allocations(), 0);
+        assert_eq!(arena.available_space(), 128);
+    }
+
+    #[test]
+    fn deterministic_padding() {
+        let sequence = [(1, 1), (8, 8), (3, 1), (4, 4), (2, 2), (16, 16), (1, 1), (32, 32)];
+        let consumed: Vec<_> = (0..2)
+            .map(|_| {
+                let arena = Arena::new_aligned(512, 32);
+                assert_eq!(arena.mem_pool.as_mut_ptr().align_offset(32), 0);
+
+                for (size, align) in sequence {
+                    arena.get_next_mem_slice(Layout::from_size_align(size, align).unwrap()).unwrap();
+                }
+                (arena.used_space(), arena.wasted_to_alignment())
+            })
+            .collect();
+
+        assert_eq!(consumed[0], consumed[1]);
+        assert_eq!(consumed[0], (96, 29));
+    }
+
+    #[cfg(feature = "debug-fill")]
+    #[test]
+    fn debug_fill() {
+        let mut arena = Arena::new(64);
+        let slice = arena.get_next_mem_slice(Layout::new::<[u8; 16]>()).unwrap();
+        assert!(unsafe { slice.as_ref() }.iter().all(|&byte| byte == DEBUG_FILL_ALLOCATED));
+
+        // zeroed allocations are still zeroed
+        let zeroed = arena.allocate_zeroed(Layout::new::<[u8; 16]>()).unwrap();
+        assert!(unsafe { zeroed.as_ref() }.iter().all(|&byte| byte == 0));
+
+        arena.reset();
+        #[cfg(feature = "asan")]
+        arena.unpoison_pool();
+        assert!(unsafe { arena.mem_pool.as_ref() }.iter().all(|&byte| byte == DEBUG_FILL_FREED));
+    }
+
+    #[test]
+    fn try_new() {
+        let arena = Arena::try_new(64).unwrap();
+        assert_eq!(arena.capacity(), 64);
+
+        let err = Arena::try_new(usize::MAX).err().unwrap();
+        assert_eq!(err.kind, ArenaErrorKind::Overflow);
+
+        // can't be allocated by any system without overcommitting that much memory
+        #[cfg(not(miri))]
+        {
+            let err = Arena::try_new(isize::MAX as usize).err().unwrap();
+            assert_eq!(err.kind, ArenaErrorKind::PoolAllocationFailed);
+            assert_eq!(err.layout.size(), isize::MAX as usize);
+        }
+    }
+
+    #[test]
+    fn allocate_zeroed() {
+        let arena_alloc = ArenaAllocator::new(Arena::new(64));
+        let mut vec = Vec::<u8, ArenaAllocator>::with_capacity_in(32, arena_alloc.clone());
+        vec.extend([0xFF; 32]);
+        drop(vec);
+
+        // the memory was dirtied by the previous allocation
+        let zeroed = arena_alloc.allocate_zeroed(Layout::new::<[u8; 48]>()).unwrap();
+        assert!(unsafe { zeroed.as_ref() }.iter().all(|&byte| byte == 0));
+    }
+
+    #[test]
+    fn cache_line_aligned_pool() {
+        #[repr(align(64))]
+        struct CacheLine([u8; 64]);
+
+        let arena_alloc = ArenaAllocator::new(Arena::new_aligned(64 * 16, 64));
+        let lines: Vec<_> = (0..16).map(|i| Box::new_in(CacheLine([i; 64]), arena_alloc.clone())).collect();
+        assert!(lines.iter().enumerate().all(|(i, line)| line.0 == [i as u8; 64]));
+
+        // the pool fits exactly 16 cache lines since none of them needed padding
+        let arena = unsafe { arena_alloc.get_arena() };
+        assert_eq!(arena.wasted_to_alignment(), 0);
+        assert_eq!(arena.available_space(), 0);
+    }
+
+    #[test]
+    fn aligned_reset_and_shrink() {
+        let mut arena = Arena::new_aligned(4096, 4096);
+        write_bytes(&arena, &[0xAA; 1000]);
+        arena.reset_and_shrink(100).unwrap();
+        assert_eq!(arena.capacity(), 100);
+        assert_eq!(arena.mem_pool.as_mut_ptr().align_offset(4096), 0);
+
+        arena.reset_and_shrink(0).unwrap();
+        assert_eq!(arena.capacity(), 0);
+        assert!(Arena::new_aligned(0, 64).get_next_mem_slice(Layout::new::<u8>()).is_err());
+    }
+
+    #[test]
+    fn requested_capacity() {
+        let arena = Arena::new(100);
+        assert_eq!((arena.requested_capacity(), arena.capacity()), (100, 100));
+    }
+
+    #[cfg(all(any(unix, windows), feature = "std"))]
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn mmap_requested_capacity() {
+        let page_size = sys::page_size();
+        let mut arena = Arena::new_mmap_with(page_size + 1, MmapOptions::new()).unwrap();
+        assert_eq!(arena.requested_capacity(), page_size + 1);
+        assert_eq!(arena.capacity(), 2 * page_size);
+        assert_eq!(arena.available_space(), 2 * page_size);
+
+        arena.reset_and_shrink(10).unwrap();
+        assert_eq!(arena.requested_capacity(), 10);
+        assert_eq!(arena.capacity(), page_size);
+    }
+
+    #[cfg(all(any(unix, windows), feature = "std"))]
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn mmap_commit_step() {
+        let page_size = sys::page_size();
+        let step = 4 * page_size;
+        let arena = Arena::new_mmap_with(64 * page_size, MmapOptions::new().commit_step(step)).unwrap();
+        assert_eq!(arena.capacity(), 64 * page_size);
+        assert_eq!(arena.committed(), step);
+
+        // stays inside the first step
+        write_bytes(&arena, &vec![0xAA; 3 * page_size]);
+        assert_eq!(arena.committed(), step);
+
+        // crosses into the second step
+        write_bytes(&arena, &vec![0xBB; 2 * page_size]);
+        assert_eq!(arena.committed(), 2 * step);
+
+        // skips ahead multiple steps at once
+        write_bytes(&arena, &vec![0xCC; 10 * page_size]);
+        assert_eq!(arena.committed(), 4 * step);
+
+        assert!(arena.get_next_mem_slice(Layout::array::<u8>(64 * page_size).unwrap()).is_err());
+        assert_eq!(arena.committed(), 4 * step);
+    }
+
+    #[cfg(all(unix, feature = "std"))]
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn mmap_lazy_commit() {
+        // only the touched pages get committed, so reserving 16 GiB is cheap
+        let arena_alloc = ArenaAllocator::new(Arena::new_mmap(16 << 30).unwrap());
+        assert_eq!(unsafe { arena_alloc.get_arena() }.capacity(), 16 << 30);
+
+        let mut vec = Vec::<u64, ArenaAllocator>::with_capacity_in(1 << 17, arena_alloc.clone());
+        vec.extend(0..1 << 17);
+        assert_eq!(vec[(1 << 17) - 1], (1 << 17) - 1);
+        assert_eq!(unsafe { arena_alloc.get_arena() }.used_space(), 1 << 20);
+    }
+
+    #[cfg(all(any(unix, windows), feature = "std"))]
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn mmap_without_commit_step() {
+        let arena_alloc = ArenaAllocator::new(Arena::new_mmap(1 << 20).unwrap());
+        assert_eq!(unsafe { arena_alloc.get_arena() }.committed(), 1 << 20);
+
+        let mut vec = Vec::<u64, ArenaAllocator>::with_capacity_in(1024, arena_alloc);
+        vec.extend(0..1024);
+        assert_eq!(vec[1023], 1023);
+    }
+
+    #[test]
+    fn huge_alignment() {
+        let align = 1 << 20;
+        let arena = Arena::new(3 * align);
+        let slice = arena.get_next_mem_slice(Layout::from_size_align(4096, align).unwrap()).unwrap();
+        assert_eq!(slice.as_mut_ptr().align_offset(align), 0);
+        assert_eq!(slice.len(), 4096);
+
+        let pool_start = arena.mem_pool.as_mut_ptr().addr();
+        let slice_start = slice.as_mut_ptr().addr();
+        assert!(slice_start >= pool_start && slice_start + 4096 <= pool_start + arena.capacity());
+
+        // an alignment that doesn't fit into the pool fails without overflowing
+        let arena = Arena::new(64);
+        assert!(arena.get_next_mem_slice(Layout::from_size_align(1, 1 << 40).unwrap()).is_err());
+        assert_eq!(arena.last_error_kind(), Some(ArenaErrorKind::AlignmentUnsatisfiable));
+    }
+
+    #[cfg(all(any(unix, windows), feature = "std"))]
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn mmap_huge_alignment() {
+        let align = 2 << 20;
+        let arena = Arena::new_mmap_with(align, MmapOptions::new().align(align)).unwrap();
+        assert_eq!(arena.capacity(), align);
+
+        // no padding is needed since the whole pool is aligned
+        let slice = arena.get_next_mem_slice(Layout::from_size_align(align, align).unwrap()).unwrap();
+        assert_eq!(slice.as_mut_ptr().align_offset(align), 0);
+        assert_eq!(arena.wasted_to_alignment(), 0);
+
+        assert!(Arena::new_mmap_with(4096, MmapOptions::new().align(3)).is_err());
+    }
+
+    #[cfg(all(target_os = "linux", feature = "std"))]
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn mmap_huge_pages() {
+        let huge_page = 2 << 20;
+        let arena = Arena::new_mmap_with(huge_page + 1, MmapOptions::new().huge_pages(true)).unwrap();
+        assert_eq!(arena.capacity(), 2 * huge_page);
+        assert_eq!(arena.requested_capacity(), huge_page + 1);
+
+        let slice = arena.get_next_mem_slice(Layout::new::<[u8; 4096]>()).unwrap();
+        assert_eq!(slice.as_mut_ptr().align_offset(huge_page), 0);
+        unsafe { slice.as_mut_ptr().write_bytes(1, 4096) };
+    }
+
+    #[cfg(all(target_os = "linux", feature = "std"))]
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn mmap_numa_policy() {
+        for policy in [NumaPolicy::Node(0), NumaPolicy::Interleave] {
+            let arena = Arena::new_mmap_with(1 << 16, MmapOptions::new().numa(policy)).unwrap();
+            let slice = arena.get_next_mem_slice(Layout::new::<[u8; 4096]>()).unwrap();
+            unsafe { slice.as_mut_ptr().write_bytes(1, 4096) };
+        }
+        assert!(Arena::new_mmap_with(1 << 16, MmapOptions::new().numa(NumaPolicy::Node(1 << 20))).is_err());
+    }
+
+    #[cfg(all(target_os = "linux", feature = "std"))]
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn mmap_policy_survives_trim() {
+        let page_size = sys::page_size();
+        let options = MmapOptions::new().commit_step(page_size).huge_pages(true).numa(NumaPolicy::Node(0));
+        let mut arena = Arena::new_mmap_with(2 << 20, options).unwrap();
+        write_bytes(&arena, &vec![0xAA; 10 * page_size]);
+        arena.reset();
+        write_bytes(&arena, &[0xBB; 8]);
+        assert!(arena.trim().unwrap() > 0);
+        write_bytes(&arena, &vec![0xCC; 4 * page_size]);
+
+        // every mapping of the pool still has the NUMA policy and the huge page advice
+        let pool = arena.base().as_ptr().addr()..arena.base().as_ptr().addr() + arena.capacity();
+        let in_pool = |line: &str| {
+            let start = line.split(['-', ' ']).next().unwrap();
+            usize::from_str_radix(start, 16).is_ok_and(|start| pool.contains(&start))
+        };
+        let numa_maps = std::fs::read_to_string("/proc/self/numa_maps").unwrap();
+        let policies: Vec<_> = numa_maps.lines().filter(|line| in_pool(line)).collect();
+        assert!(!policies.is_empty());
+        assert!(policies.iter().all(|line| line.contains(" bind:0 ")));
+
+        let smaps = std::fs::read_to_string("/proc/self/smaps").unwrap();
+        let mut current = false;
+        let mut advised = 0;
+        for line in smaps.lines() {
+            if let Some(flags) = line.strip_prefix("VmFlags:") {
+                if current {
+                    assert!(flags.split_whitespace().any(|flag| flag == "hg"));
+                    advised += 1;
+                }
+            } else if line.split_whitespace().next().is_some_and(|range| range.contains('-')) {
+                current = in_pool(line);
+            }
+        }
+        assert_eq!(advised, policies.len());
+    }
+
+    #[cfg(all(unix, feature = "std"))]
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn mmap_guard_pages() {
+        use std::{os::unix::process::ExitStatusExt, process::Command};
+
+        let page_size = sys::page_size();
+        let options = MmapOptions::new().guard_pages(true).align(1 << 20);
+        let mut arena = Arena::new_mmap_with(4 * page_size, options).unwrap();
+        let slice = arena.get_next_mem_slice(Layout::from_size_align(4 * page_size, 1 << 20).unwrap()).unwrap();
+        unsafe { slice.as_mut_ptr().write_bytes(1, 4 * page_size) };
+        arena.reset_and_shrink(page_size).unwrap();
+        write_bytes(&arena, &vec![2; page_size]);
+
+        // writing past the end of the pool has to crash, which is checked in a child process.
+        if std::env::var_os("XS_ARENA_GUARD_CHILD").is_some() {
+            unsafe { arena.mem_pool.as_mut_ptr().add(page_size).write_volatile(0) };
+            return;
+        }
+
+        let status = Command::new(std::env::current_exe().unwrap())
+            .args(["test::mmap_guard_pages", "--exact", "--test-threads=1"])
+            .env("XS_ARENA_GUARD_CHILD", "1")
+            .output()
+            .unwrap()
+            .status;
+        assert_eq!(status.signal(), Some(11));
+    }
+
+    #[test]
+    fn reset_and_shrink() {
+        let mut arena = Arena::new(1024);
+        write_bytes(&arena, &[0xAA; 900]);
+
+        arena.reset_and_shrink(128).unwrap();
+        assert_eq!(arena.capacity(), 128);
+        assert_eq!(arena.available_space(), 128);
+        write_bytes(&arena, &[0xBB; 128]);
+        assert!(arena.get_next_mem_slice(Layout::new::<u8>()).is_err());
+
+        // growing is not possible
+        arena.reset_and_shrink(4096).unwrap();
+        assert_eq!(arena.capacity(), 128);
+
+        arena.reset_and_shrink(0).unwrap();
+        assert_eq!(arena.capacity(), 0);
+
+        let parent = Arena::new(64);
+        let block = parent.get_next_mem_slice(Layout::new::<[u8; 64]>()).unwrap();
+        let mut sub_arena = unsafe { Arena::from_external(block) };
+        write_bytes(&sub_arena, &[0xCC; 8]);
+        assert!(sub_arena.reset_and_shrink(8).is_err());
+        assert_eq!(sub_arena.available_space(), 64);
+    }
+
+    #[cfg(all(any(unix, windows), feature = "std"))]
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn mmap_reset_and_shrink() {
+        let page_size = sys::page_size();
+        let mut arena = Arena::new_mmap_with(16 * page_size, MmapOptions::new().commit_step(page_size)).unwrap();
+        write_bytes(&arena, &vec![0xAA; 10 * page_size]);
+
+        arena.reset_and_shrink(page_size + 1).unwrap();
+        assert_eq!(arena.capacity(), 2 * page_size);
+        assert_eq!(arena.committed(), 2 * page_size);
+        write_bytes(&arena, &vec![0xBB; 2 * page_size]);
+    }
+
+    #[cfg(all(any(unix, windows), feature = "std"))]
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn mmap_trim() {
+        let page_size = sys::page_size();
+        let mut arena = Arena::new_mmap_with(16 * page_size, MmapOptions::new().commit_step(page_size)).unwrap();
+        write_bytes(&arena, &vec![0xAA; 10 * page_size]);
+        assert_eq!(arena.trim().unwrap(), 0);
+
+        arena.reset();
+        write_bytes(&arena, &[0xBB; 8]);
+        assert_eq!(arena.trim().unwrap(), 9 * page_size);
+        assert_eq!((arena.committed(), arena.capacity()), (page_size, 16 * page_size));
+        write_bytes(&arena, &vec![0xCC; 4 * page_size]);
+
+        // fully accessible pools keep the trimmed pages accessible
+        let options = MmapOptions::new().trim_on_reset(2 * page_size);
+        let mut arena = Arena::new_mmap_with(8 * page_size, options).unwrap();
+        write_bytes(&arena, &vec![0xAA; 8 * page_size]);
+        arena.reset();
+        assert_ne!(unsafe { arena.base().read() }, 0);
+        #[cfg(target_os = "linux")]
+        assert_eq!(unsafe { arena.base().add(2 * page_size).read() }, 0);
+    }
+
+    #[cfg(feature = "track-threads")]
+    #[test]
+    fn allocations_by_thread() {
+        let arena = Arc::new(Arena::new(4096));
+        let join_handles: Vec<_> = (1..=4)
+            .map(|i| {
+                let arena = arena.clone();
+                thread::spawn(move || {
+                    for _ in 0..i * 10 {
+                        arena.get_next_mem_slice(Layout::new::<u32>()).unwrap();
+                    }
+                    thread::current().id()
+                })
+            })
+            .collect();
+
+        let thread_ids: Vec<_> = join_handles.into_iter().map(|j| j.join().unwrap()).collect();
+        let allocations_by_thread = arena.allocations_by_thread();
+        assert_eq!(allocations_by_thread.len(), 4);
+
+        for (i, thread_id) in thread_ids.iter().enumerate() {
+            assert_eq!(allocations_by_thread[thread_id], (i + 1) * 10);
+        }
+    }
+
+    #[test]
+    fn borrowed_arenas_allocate() {
+        let arena = Arena::new(256);
+        let mut vec = Vec::new_in(&arena);
+        vec.extend(0u32..8);
+        let boxed = Box::new_in(5u64, &arena);
+        vec.extend(8..16);
+        assert!(vec.iter().copied().eq(0..16));
+        assert_eq!(arena.live_allocations(), 2);
+        drop((vec, boxed));
+        assert_eq!(arena.used_space(), 0);
+
+        let shared = Arc::new(Arena::new(64));
+        let alloc = ArenaAllocator::from(shared.clone());
+        let value = Box::new_in(1u32, alloc);
+        assert_eq!(shared.live_allocations(), 1);
+        drop(value);
+        assert_eq!(shared.live_allocations(), 0);
+    }
+
+    // the following tests are aimed at `cargo miri test` with `MIRIFLAGS=-Zmiri-strict-provenance`,
+    // which checks that blocks only carry the provenance of the memory pool and don't alias each other.
+    #[test]
+    fn miri_disjoint_blocks() {
+        let arena = Arena::new(64);
+        let first = unsafe { arena.get_next_mem_slice(Layout::new::<[u8; 16]>()).unwrap().as_mut() };
+        let second = unsafe { arena.get_next_mem_slice(Layout::new::<[u8; 16]>()).unwrap().as_mut() };
+
+        // both blocks are written alternately while the references to them are live.
+        for i in 0..16 {
+            first[i] = i as u8;
+            second[i] = !(i as u8);
+        }
+        assert!(first.iter().zip(second.iter()).all(|(a, b)| *a == !*b));
+
+        let back = unsafe { arena.alloc_back(Layout::new::<[u8; 16]>()).unwrap().as_mut() };
+        back.fill(0xFF);
+        assert_eq!(first[15], 15);
+    }
+
+    #[test]
+    fn miri_reuse_and_grow() {
+        let arena_alloc = ArenaAllocator::new(Arena::new(256));
+        let mut vec = Vec::<u32, _>::with_capacity_in(2, arena_alloc.clone());
+        vec.extend(0..2);
+        // growing the most recent allocation extends its block in place.
+        let first = vec.as_ptr();
+        vec.extend(2..8);
+        assert_eq!(vec.as_ptr(), first);
+        assert_eq!(vec, [0, 1, 2, 3, 4, 5, 6, 7]);
+
+        let other = Box::new_in(1u64, arena_alloc.clone());
+        vec.extend(8..32);
+        assert_eq!(*other, 1);
+        assert!(vec.iter().copied().eq(0..32));
+
+        // a block handed back with `free_last` is handed out again.
+        drop(other);
+        let block = arena_alloc.arena.get_next_mem_slice(Layout::new::<u64>()).unwrap();
+        unsafe { block.as_mut_ptr().cast::<u64>().write_unaligned(2) };
+        unsafe { arena_alloc.arena.free_last(block.as_non_null_ptr(), Layout::new::<u64>()) };
+    }
+
+    #[test]
+    fn miri_drop_paths() {
+        let mut arena = Arena::new(256);
+        arena.alloc_droppable(String::from("dropped on reset"));
+        arena.reset();
+
+        arena.alloc_droppable(vec![1u8, 2, 3]);
+        write_bytes(&arena, &[1; 8]);
+        arena.reset_and_shrink(64).unwrap();
+        let value = arena.alloc_droppable(Box::new(4u32));
+        **value += 1;
+        assert_eq!(**value, 5);
+
+        let arena_alloc = ArenaAllocator::new(arena);
+        let boxed = Box::new_in(String::from("dropped with the allocator"), arena_alloc.clone());
+        drop(arena_alloc);
+        assert_eq!(boxed.len(), 26);
+    }
+
+    fn write_bytes(arena: &Arena, bytes: &[u8]) {
+        let slice = arena.get_next_mem_slice(Layout::for_value(bytes)).unwrap();
+        unsafe { slice.as_mut_ptr().copy_from_nonoverlapping(bytes.as_ptr(), bytes.len()) };
+    }
+
+    fn spawn_allocating_thread(arena_allocator: ArenaAllocator) -> JoinHandle<()> {
+        thread::spawn(move || {
+            let mut vec1 = Vec::<u8, ArenaAllocator>::with_capacity_in(2, arena_allocator.clone());
+            let mut vec2 = Vec::<u32, ArenaAllocator>::with_capacity_in(1, arena_allocator);
+            vec1.push(0xAA);
+            vec1.push(0x55);
+            vec2.push(0xFFFFFFFF);
+        })
+    }
+}
+
+/// model checks the lock-free allocation path, run with `RUSTFLAGS="--cfg loom" cargo test --release loom_test`.
+#[cfg(all(test, loom))]
+mod loom_test {
+    use alloc::vec::Vec;
+    use loom::{sync::Arc, thread};
+
+    use super::*;
+
+    const BLOCK: Layout = Layout::new::<[u8; 8]>();
+
+    /// allocates a block on a new thread and returns its offset in the memory pool, if any.
+    fn spawn_allocation(arena: &Arc<Arena>) -> thread::JoinHandle<Option<usize>> {
+        let arena = arena.clone();
+        thread::spawn(move || arena.allocate_with_offset(BLOCK).ok().map(|(_, offset)| offset))
+    }
+
+    #[test]
+    fn concurrent_allocations_are_disjoint() {
+        loom::model(|| {
+            let arena = Arc::new(Arena::new(16));
+            let threads = [spawn_allocation(&arena), spawn_allocation(&arena)];
+            let mut offsets: Vec<_> = threads.into_iter().map(|thread| thread.join().unwrap().unwrap()).collect();
+
+            offsets.sort();
+            assert_eq!(offsets, [0, 8]);
+            assert_eq!(arena.used_space(), 16);
+        });
+    }
+
+    #[test]
+    fn contended_exhaustion() {
+        loom::model(|| {
+            let arena = Arc::new(Arena::new(12));
+            let threads = [spawn_allocation(&arena), spawn_allocation(&arena)];
+            let results: Vec<_> = threads.into_iter().map(|thread| thread.join().unwrap()).collect();
+
+            assert_eq!(results.iter().flatten().count(), 1);
+            assert_eq!(arena.used_space(), 8);
+        });
+    }
+
+    #[test]
+    fn reclaim_races_with_allocation() {
+        loom::model(|| {
+            let arena = Arc::new(Arena::new(16));
+            let (_, first) = arena.allocate_with_offset(BLOCK).unwrap();
+
+            let freeing = {
+                let arena = arena.clone();
+                thread::spawn(move || unsafe { arena.free_last(arena.mem_pool.as_non_null_ptr().add(first), BLOCK) })
+            };
+            let (_, second) = arena.allocate_with_offset(BLOCK).unwrap();
+            freeing.join().unwrap();
+
+            // the second block is still live, so the offset must not have been rewound below its end.
+            assert!(arena.used_space() >= second + BLOCK.size());
+            assert_eq!(arena.live_allocations(), 1);
+        });
     }
 }
@@ -0,0 +1,124 @@
+use alloc::{boxed::Box, rc::Rc};
+use core::{
+    alloc::{AllocError, Allocator, Layout},
+    cell::Cell,
+    ptr::NonNull,
+};
+
+/// single threaded variant of `Arena` that bumps a plain offset instead of running a compare_exchange loop.
+/// it is neither `Send` nor `Sync`.
+pub struct LocalArena {
+    mem_pool: NonNull<[u8]>,
+    offset: Cell<usize>,
+}
+
+impl LocalArena {
+    /// create a new arena with the passed capacity in bytes. the memory pool is not zeroed up front.
+    pub fn new(capacity: usize) -> Self {
+        let buf = Box::<[u8]>::new_uninit_slice(capacity);
+        Self {
+            mem_pool: unsafe { NonNull::new_unchecked(Box::into_raw(buf) as *mut [u8]) },
+            offset: Cell::new(0),
+        }
+    }
+
+    /// returns the maximum capacity of the arena, including the space thats already used.
+    pub fn capacity(&self) -> usize {
+        self.mem_pool.len()
+    }
+
+    /// returns the available space of the arena in bytes.
+    pub fn available_space(&self) -> usize {
+        self.capacity() - self.offset.get()
+    }
+
+    /// returns the used space of the arena in bytes, including the padding needed for alignment.
+    pub fn used_space(&self) -> usize {
+        self.offset.get()
+    }
+
+    /// rewinds the arena to its initial state so the memory pool can be reused.
+    pub fn reset(&mut self) {
+        self.offset.set(0);
+    }
+
+    /// returns a pointer to a memory slice with the size and alignment of the passed `Layout`.
+    pub fn get_next_mem_slice(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        let offset = self.offset.get();
+        let align_offset = unsafe { self.mem_pool.as_mut_ptr().add(offset).align_offset(layout.align()) };
+
+        let start = offset.checked_add(align_offset).ok_or(AllocError)?;
+        let end = start.checked_add(layout.size()).ok_or(AllocError)?;
+
+        if end > self.capacity() {
+            return Err(AllocError);
+        }
+
+        self.offset.set(end);
+        unsafe { Ok(self.mem_pool.get_unchecked_mut(start..end)) }
+    }
+}
+
+impl Drop for LocalArena {
+    fn drop(&mut self) {
+        unsafe {
+            let _: Box<[u8]> = Box::from_raw(self.mem_pool.as_ptr());
+        }
+    }
+}
+
+/// bump style allocator for a `LocalArena`.
+#[derive(Clone)]
+pub struct LocalArenaAllocator {
+    arena: Rc<LocalArena>,
+}
+
+impl LocalArenaAllocator {
+    pub fn new(arena: LocalArena) -> Self {
+        Self { arena: Rc::new(arena) }
+    }
+
+    /// returns the arena this allocator allocates from.
+    pub fn arena(&self) -> &LocalArena {
+        &self.arena
+    }
+}
+
+unsafe impl Allocator for LocalArenaAllocator {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.arena.get_next_mem_slice(layout)
+    }
+
+    unsafe fn deallocate(&self, _ptr: NonNull<u8>, _layout: Layout) {}
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn allocation() {
+        let alloc = LocalArenaAllocator::new(LocalArena::new(64));
+        let mut vec1 = Vec::<u8, LocalArenaAllocator>::with_capacity_in(3, alloc.clone());
+        let mut vec2 = Vec::<u32, LocalArenaAllocator>::with_capacity_in(2, alloc.clone());
+        vec1.extend([1, 2, 3]);
+        vec2.extend([4, 5]);
+
+        assert_eq!(vec1, [1, 2, 3]);
+        assert_eq!(vec2, [4, 5]);
+        assert_eq!(vec2.as_ptr().align_offset(4), 0);
+        // the padding in front of vec2 depends on the alignment of the memory pool.
+        assert_eq!(alloc.arena().used_space(), vec2.as_ptr().addr() + 8 - vec1.as_ptr().addr());
+    }
+
+    #[test]
+    fn exhaustion_and_reset() {
+        let mut arena = LocalArena::new(16);
+        arena.get_next_mem_slice(Layout::new::<[u8; 10]>()).unwrap();
+        assert!(arena.get_next_mem_slice(Layout::new::<[u8; 8]>()).is_err());
+        assert_eq!(arena.available_space(), 6);
+
+        arena.reset();
+        assert!(arena.get_next_mem_slice(Layout::new::<[u8; 16]>()).is_ok());
+    }
+}
@@ -0,0 +1,130 @@
+use alloc::vec::Vec;
+use core::{mem::ManuallyDrop, ops::Deref};
+
+use crate::{sync::Mutex, Arena, Handle};
+
+/// free list of arenas that are reset and reused instead of being freed, e.g. one arena per request in a server.
+/// this avoids allocating and freeing a large memory pool for every request.
+pub struct ArenaPool {
+    arenas: Mutex<Vec<Arena>>,
+    arena_capacity: usize,
+    max_pooled: usize,
+}
+
+impl ArenaPool {
+    /// create a new pool that hands out arenas with the passed capacity in bytes
+    /// and keeps at most `max_pooled` returned arenas around for reuse.
+    pub fn new(arena_capacity: usize, max_pooled: usize) -> Self {
+        Self {
+            arenas: Mutex::new(Vec::new()),
+            arena_capacity,
+            max_pooled,
+        }
+    }
+
+    /// returns a reset arena from the free list, or a new one if the free list is empty.
+    /// the arena goes back to the pool when the returned guard is dropped.
+    pub fn acquire(&self) -> PooledArena<'_> {
+        let arena = self.arenas.lock().pop().unwrap_or_else(|| Arena::new(self.arena_capacity));
+        PooledArena {
+            arena: ManuallyDrop::new(arena),
+            pool: self,
+        }
+    }
+
+    /// returns the amount of arenas that are waiting in the free list.
+    pub fn pooled(&self) -> usize {
+        self.arenas.lock().len()
+    }
+
+    /// returns the capacity of the arenas handed out by the pool.
+    pub fn arena_capacity(&self) -> usize {
+        self.arena_capacity
+    }
+}
+
+/// arena borrowed from an `ArenaPool`, which is reset and returned to the pool on drop.
+/// it only hands out `&Arena`, so the arena that goes back to the pool is always the one the pool created.
+///
+/// ```compile_fail
+/// let pool = xs_arena_allocator::ArenaPool::new(64, 1);
+/// let mut arena = pool.acquire();
+/// *arena = xs_arena_allocator::Arena::new(64);
+/// ```
+pub struct PooledArena<'a> {
+    arena: ManuallyDrop<Arena>,
+    pool: &'a ArenaPool,
+}
+
+impl PooledArena<'_> {
+    /// see `Arena::reset`.
+    pub fn reset(&mut self) {
+        self.arena.reset();
+    }
+
+    /// see `Arena::scope`.
+    pub fn scope<R>(&mut self, f: impl FnOnce(&Arena) -> R) -> R {
+        self.arena.scope(f)
+    }
+
+    /// see `Arena::get_mut`.
+    pub fn get_mut<T>(&mut self, handle: Handle<T>) -> Option<&mut T> {
+        self.arena.get_mut(handle)
+    }
+}
+
+impl Deref for PooledArena<'_> {
+    type Target = Arena;
+
+    fn deref(&self) -> &Self::Target {
+        &self.arena
+    }
+}
+
+impl Drop for PooledArena<'_> {
+    fn drop(&mut self) {
+        let mut arena = unsafe { ManuallyDrop::take(&mut self.arena) };
+        arena.reset();
+
+        let mut arenas = self.pool.arenas.lock();
+        if arenas.len() < self.pool.max_pooled {
+            arenas.push(arena);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::alloc::Layout;
+
+    use super::*;
+
+    #[test]
+    fn arenas_are_reused() {
+        let pool = ArenaPool::new(64, 1);
+
+        let first = pool.acquire();
+        first.get_next_mem_slice(Layout::new::<[u8; 32]>()).unwrap();
+        let base = first.mem_pool.as_mut_ptr();
+        let second = pool.acquire();
+        drop(first);
+        assert_eq!(pool.pooled(), 1);
+
+        // the pool is full, so the second arena is freed
+        drop(second);
+        assert_eq!(pool.pooled(), 1);
+
+        let reused = pool.acquire();
+        assert_eq!(reused.mem_pool.as_mut_ptr(), base);
+        assert_eq!(reused.used_space(), 0);
+        assert_eq!(reused.capacity(), 64);
+        assert_eq!(pool.pooled(), 0);
+
+        let mut reused = reused;
+        let handle = reused.insert(1u32);
+        *reused.get_mut(handle).unwrap() += 1;
+        assert_eq!(reused.scope(|arena| *arena.alloc(2u32)) + reused.get(handle).unwrap(), 4);
+        reused.reset();
+        assert_eq!(reused.used_space(), 0);
+    }
+}
@@ -0,0 +1,107 @@
+use alloc::{collections::BTreeMap, vec::Vec};
+use core::{fmt, panic::Location};
+
+use crate::Arena;
+
+/// allocations made from one place in the code, see `Arena::profile_report`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct CallSite {
+    pub location: &'static Location<'static>,
+    /// amount of successful allocations.
+    pub allocations: usize,
+    /// bytes the allocations took from the memory pool, including rounding but not alignment padding.
+    pub bytes: usize,
+}
+
+/// call sites that allocated from an arena, the ones that took the most bytes first.
+/// prints one line per call site when formatted with `Display`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProfileReport {
+    pub call_sites: Vec<CallSite>,
+}
+
+impl ProfileReport {
+    /// returns the sum of the bytes of all call sites.
+    pub fn total_bytes(&self) -> usize {
+        self.call_sites.iter().map(|site| site.bytes).sum()
+    }
+}
+
+impl fmt::Display for ProfileReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let total = self.total_bytes().max(1);
+        for site in &self.call_sites {
+            writeln!(
+                f,
+                "{:>10} bytes {:>5.1}% {:>8} allocations  {}",
+                site.bytes,
+                site.bytes as f64 * 100.0 / total as f64,
+                site.allocations,
+                site.location
+            )?;
+        }
+        Ok(())
+    }
+}
+
+pub(crate) type CallSites = BTreeMap<&'static Location<'static>, CallSite>;
+
+impl Arena {
+    /// returns the bytes allocated by every call site since the arena was created or reset, e.g. to find out what
+    /// fills a per-frame arena. the call site is the first caller outside of the crate, allocations made through
+    /// `Allocator` implementations are attributed to the collection that made them, like `RawVec` inside `alloc`.
+    pub fn profile_report(&self) -> ProfileReport {
+        let mut call_sites: Vec<_> = self.call_sites.lock().values().copied().collect();
+        call_sites.sort_by(|a, b| b.bytes.cmp(&a.bytes).then(a.location.cmp(b.location)));
+        ProfileReport { call_sites }
+    }
+
+    pub(crate) fn record_call_site(&self, location: &'static Location<'static>, allocations: usize, bytes: usize) {
+        let mut call_sites = self.call_sites.lock();
+        let site = call_sites.entry(location).or_insert(CallSite {
+            location,
+            allocations: 0,
+            bytes: 0,
+        });
+        site.allocations += allocations;
+        site.bytes += bytes;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::string::ToString;
+    use core::alloc::Layout;
+
+    use super::*;
+
+    #[test]
+    fn groups_bytes_by_call_site() {
+        let mut arena = Arena::new(1024);
+        let small = Location::caller();
+        for _ in 0..4 {
+            arena.alloc(0u32);
+        }
+        let large = Location::caller();
+        arena.alloc_slice_copy(&[0u8; 100]);
+        arena.get_next_mem_slice(Layout::new::<u64>()).unwrap();
+        assert!(arena.try_allocate(Layout::new::<[u8; 2048]>()).is_err());
+
+        let report = arena.profile_report();
+        assert_eq!(report.call_sites.len(), 3);
+        assert_eq!(report.total_bytes(), 4 * 4 + 100 + 8);
+
+        let [first, second, third] = report.call_sites[..] else {
+            unreachable!()
+        };
+        assert_eq!((first.allocations, first.bytes), (1, 100));
+        assert_eq!(first.location.line(), large.line() + 1);
+        assert_eq!((second.allocations, second.bytes), (4, 16));
+        assert_eq!(second.location.line(), small.line() + 2);
+        assert_eq!(third.location.file(), file!());
+        assert!(report.to_string().lines().next().unwrap().contains("100 bytes"));
+
+        arena.reset();
+        assert!(arena.profile_report().call_sites.is_empty());
+    }
+}
@@ -0,0 +1,100 @@
+use std::{
+    io,
+    ops::Deref,
+    sync::atomic::{self, Ordering},
+};
+
+use crate::{sys, Arena};
+
+/// arena for secrets like decrypted key material.
+/// the memory pool is locked into memory so it is never written to swap, and the whole pool is wiped
+/// with writes the compiler can't elide on `reset` and on drop.
+///
+/// only shared access to the arena is handed out, so the memory pool can't be reset without being wiped.
+pub struct SecureArena {
+    arena: Arena,
+}
+
+impl SecureArena {
+    /// create a new secure arena with the passed capacity in bytes, rounded up to the page size.
+    /// fails if the memory can't be locked, e.g. because `RLIMIT_MEMLOCK` is too small.
+    pub fn new(capacity: usize) -> io::Result<Self> {
+        let arena = Arena::new_mmap(capacity)?;
+        unsafe { sys::lock(arena.mem_pool.as_mut_ptr(), arena.capacity())? };
+        Ok(Self { arena })
+    }
+
+    /// additionally excludes the memory pool from core dumps with `madvise(MADV_DONTDUMP)`.
+    /// fails with `ErrorKind::Unsupported` on systems other than linux.
+    pub fn exclude_from_core_dumps(self) -> io::Result<Self> {
+        unsafe { sys::exclude_from_dumps(self.arena.mem_pool.as_mut_ptr(), self.arena.capacity())? };
+        Ok(self)
+    }
+
+    /// wipes the memory pool and resets the arena.
+    pub fn reset(&mut self) {
+        self.arena.reset();
+        self.wipe();
+    }
+
+    fn wipe(&mut self) {
+        #[cfg(feature = "asan")]
+        self.arena.unpoison_pool();
+
+        let base = self.arena.mem_pool.as_mut_ptr();
+        for i in 0..self.arena.capacity() {
+            unsafe { base.add(i).write_volatile(0) };
+        }
+        atomic::compiler_fence(Ordering::SeqCst);
+
+        #[cfg(feature = "asan")]
+        self.arena.poison_pool();
+    }
+}
+
+impl Deref for SecureArena {
+    type Target = Arena;
+
+    fn deref(&self) -> &Self::Target {
+        &self.arena
+    }
+}
+
+impl Drop for SecureArena {
+    fn drop(&mut self) {
+        // destructors of registered values may still read the memory pool.
+        self.arena.run_drops(0);
+        self.wipe();
+        unsafe { sys::unlock(self.arena.mem_pool.as_mut_ptr(), self.arena.capacity()) };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::alloc::Layout;
+
+    use super::*;
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn wipes_on_reset() {
+        let mut arena = SecureArena::new(4096).unwrap();
+        let key = arena.get_next_mem_slice(Layout::new::<[u8; 32]>()).unwrap();
+        unsafe { key.as_mut_ptr().write_bytes(0x5A, 32) };
+
+        arena.reset();
+        assert_eq!(arena.used_space(), 0);
+        // peeking at memory after the reset is exactly what the poisoning reports.
+        #[cfg(feature = "asan")]
+        crate::asan::unpoison(key.as_mut_ptr(), 32);
+        assert!(unsafe { key.as_ref() }.iter().all(|&byte| byte == 0));
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn exclude_from_core_dumps() {
+        let arena = SecureArena::new(4096).unwrap().exclude_from_core_dumps().unwrap();
+        assert_eq!(*arena.alloc(7u32), 7);
+    }
+}
@@ -0,0 +1,190 @@
+use std::{
+    alloc::{AllocError, Allocator, Layout},
+    ptr::NonNull,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    thread,
+};
+
+use crate::{sync::Mutex, Arena};
+
+/// private block of a shard, `start..end` relative to the start of the memory pool.
+#[derive(Default)]
+struct Block {
+    start: usize,
+    end: usize,
+}
+
+/// padded to its own cache line, so shards used by different threads don't slow each other down.
+#[derive(Default)]
+#[repr(align(128))]
+struct Shard(Mutex<Block>);
+
+struct Shards {
+    arena: Arena,
+    block_size: usize,
+    /// amount of blocks claimed from the arena, each of them counts as a live allocation of the arena.
+    claimed_blocks: AtomicUsize,
+    shards: Box<[Shard]>,
+}
+
+/// allocator that lets every thread bump-allocate from a private block claimed from a shared arena.
+/// the shared offset is only touched when a block runs out, which removes the contention of many threads
+/// allocating from the same arena at the same time.
+///
+/// allocations larger than a quarter of the block size are served by the arena directly.
+/// memory inside the blocks is only reclaimed by `try_reset`, the rest of a block that is too small for the next
+/// allocation is wasted.
+#[derive(Clone)]
+pub struct ShardedArenaAllocator {
+    inner: Arc<Shards>,
+}
+
+impl ShardedArenaAllocator {
+    /// creates an allocator that claims blocks of `block_size` bytes from the passed arena.
+    pub fn new(arena: Arena, block_size: usize) -> Self {
+        let shard_count = thread::available_parallelism().map_or(8, |count| count.get() * 2);
+        Self {
+            inner: Arc::new(Shards {
+                arena,
+                block_size,
+                claimed_blocks: AtomicUsize::new(0),
+                shards: (0..shard_count).map(|_| Shard::default()).collect(),
+            }),
+        }
+    }
+
+    /// returns the arena the blocks are claimed from.
+    pub fn arena(&self) -> &Arena {
+        &self.inner.arena
+    }
+
+    /// resets the arena and empties the blocks of the shards if this is the only handle to the allocator and every
+    /// direct allocation of the arena was freed. returns whether the arena was reset.
+    pub fn try_reset(&mut self) -> bool {
+        let Some(inner) = Arc::get_mut(&mut self.inner) else {
+            return false;
+        };
+        if inner.arena.live_allocations() != *inner.claimed_blocks.get_mut() {
+            return false;
+        }
+
+        for shard in inner.shards.iter_mut() {
+            *shard.0.get_mut() = Block::default();
+        }
+        // the claimed blocks are never deallocated, they are reclaimed together with the arena.
+        *inner.arena.live_allocations.get_mut() = 0;
+        *inner.claimed_blocks.get_mut() = 0;
+        inner.arena.reset();
+        true
+    }
+
+    fn is_direct(&self, layout: Layout) -> bool {
+        layout.size() > self.inner.block_size / 4
+    }
+
+    fn current_shard(&self) -> &Shard {
+        static NEXT_INDEX: AtomicUsize = AtomicUsize::new(0);
+        thread_local! {
+            static INDEX: usize = NEXT_INDEX.fetch_add(1, Ordering::Relaxed);
+        }
+
+        let index = INDEX.with(|index| *index);
+        &self.inner.shards[index % self.inner.shards.len()]
+    }
+}
+
+unsafe impl Allocator for ShardedArenaAllocator {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        let arena = &self.inner.arena;
+        if self.is_direct(layout) {
+            return arena.get_next_mem_slice(layout);
+        }
+
+        let base = arena.mem_pool.as_mut_ptr();
+        let mut block = self.current_shard().0.lock();
+
+        let mut start = block.start + unsafe { base.add(block.start) }.align_offset(layout.align());
+        if start.saturating_add(layout.size()) > block.end {
+            let block_layout = Layout::from_size_align(self.inner.block_size, layout.align()).map_err(|_| AllocError)?;
+            let (_, offset) = arena.allocate_with_offset(block_layout)?;
+            self.inner.claimed_blocks.fetch_add(1, Ordering::Relaxed);
+            *block = Block {
+                start: offset,
+                end: offset + self.inner.block_size,
+            };
+            start = offset;
+        }
+
+        block.start = start + layout.size();
+        unsafe { Ok(arena.mem_pool.get_unchecked_mut(start..block.start)) }
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        // allocations inside a block are only freed together with the arena.
+        if self.is_direct(layout) {
+            unsafe { self.inner.arena.free_last(ptr, layout) };
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn blocks_are_claimed_per_thread() {
+        let alloc = ShardedArenaAllocator::new(Arena::new_aligned(1 << 20, 8), 4096);
+        let join_handles: Vec<_> = (0..8u64)
+            .map(|i| {
+                let alloc = alloc.clone();
+                thread::spawn(move || {
+                    let values: Vec<_> = (0..100).map(|j| Box::new_in(i * 1000 + j, alloc.clone())).collect();
+                    assert!(values.iter().map(|value| **value).eq(i * 1000..i * 1000 + 100));
+                })
+            })
+            .collect();
+
+        join_handles.into_iter().for_each(|j| j.join().unwrap());
+
+        // every thread needs a single block for 100 u64s
+        assert!(alloc.arena().live_allocations() <= 8);
+        assert_eq!(alloc.arena().used_space() % 4096, 0);
+
+        let mut alloc = alloc;
+        let clone = alloc.clone();
+        assert!(!alloc.try_reset());
+        drop(clone);
+        assert!(alloc.try_reset());
+        assert_eq!(alloc.arena().used_space(), 0);
+        assert_eq!(*Box::new_in(5u8, alloc.clone()), 5);
+    }
+
+    #[test]
+    fn large_allocations_bypass_the_shards() {
+        let alloc = ShardedArenaAllocator::new(Arena::new(8192), 1024);
+        let mut vec = Vec::<u8, _>::with_capacity_in(2048, alloc.clone());
+        vec.extend(0..=255);
+        assert_eq!(alloc.arena().used_space(), 2048);
+
+        drop(vec);
+        assert_eq!(alloc.arena().used_space(), 0);
+    }
+
+    #[test]
+    fn direct_allocations_prevent_reset() {
+        let mut alloc = ShardedArenaAllocator::new(Arena::new(8192), 1024);
+        drop(Box::new_in(1u32, alloc.clone()));
+        let layout = Layout::new::<[u8; 512]>();
+        let direct = alloc.allocate(layout).unwrap();
+
+        assert!(!alloc.try_reset());
+        assert_eq!(alloc.arena().live_allocations(), 2);
+
+        unsafe { alloc.deallocate(direct.as_non_null_ptr(), layout) };
+        assert!(alloc.try_reset());
+        assert_eq!(alloc.arena().used_space(), 0);
+    }
+}
@@ -0,0 +1,235 @@
+use core::{
+    alloc::Layout,
+    fmt,
+    marker::PhantomData,
+    mem::ManuallyDrop,
+    ops::{Deref, DerefMut},
+    ptr::NonNull,
+};
+
+use crate::{sync::Mutex, Arena, ArenaError};
+
+/// slot of a `SlabArena`, which holds either a live value or the link to the next free slot.
+union Slot<T> {
+    value: ManuallyDrop<T>,
+    next: Option<NonNull<Slot<T>>>,
+}
+
+struct FreeList<T> {
+    head: Option<NonNull<Slot<T>>>,
+    live: usize,
+}
+
+// the free slots are only accessed while the list is locked.
+unsafe impl<T: Send> Send for FreeList<T> {}
+
+/// arena for objects of a single type that can be freed individually, e.g. entities, particles or tree nodes.
+/// freed slots are kept in an intrusive free list and reused before new memory is taken from the arena,
+/// so allocating and freeing take constant time and churn doesn't exhaust the arena.
+pub struct SlabArena<T> {
+    arena: Arena,
+    free: Mutex<FreeList<T>>,
+}
+
+impl<T> SlabArena<T> {
+    /// create a new slab arena with space for `capacity` objects.
+    ///
+    /// # Panics
+    /// panics if the slots for `capacity` objects don't fit into the address space.
+    pub fn new(capacity: usize) -> Self {
+        let layout = Layout::array::<Slot<T>>(capacity).expect("slab is too large");
+        Self {
+            arena: Arena::new_aligned(layout.size(), layout.align()),
+            free: Mutex::new(FreeList { head: None, live: 0 }),
+        }
+    }
+
+    /// moves `value` into a free slot, which is freed again when the returned box is dropped.
+    ///
+    /// # Panics
+    /// calls `handle_alloc_error` if every slot is in use.
+    pub fn alloc(&self, value: T) -> SlabBox<'_, T> {
+        match self.try_alloc(value) {
+            Ok(value) => value,
+            Err(_) => alloc::alloc::handle_alloc_error(Layout::new::<Slot<T>>()),
+        }
+    }
+
+    /// same as `alloc` but returns an error instead of panicking if every slot is in use.
+    pub fn try_alloc(&self, value: T) -> Result<SlabBox<'_, T>, ArenaError> {
+        let slot = {
+            let mut free = self.free.lock();
+            let slot = match free.head {
+                Some(slot) => {
+                    free.head = unsafe { slot.as_ref().next };
+                    slot
+                }
+                None => self.arena.try_allocate(Layout::new::<Slot<T>>())?.cast(),
+            };
+            free.live += 1;
+            slot
+        };
+
+        unsafe {
+            slot.write(Slot {
+                value: ManuallyDrop::new(value),
+            })
+        };
+        Ok(SlabBox {
+            slot,
+            slab: self,
+            _value: PhantomData,
+        })
+    }
+
+    /// returns the amount of objects that are currently allocated.
+    pub fn len(&self) -> usize {
+        self.free.lock().live
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// returns the amount of objects the slab arena has space for.
+    pub fn capacity(&self) -> usize {
+        self.arena.capacity() / Layout::new::<Slot<T>>().size()
+    }
+
+    /// frees every slot at once. values that were leaked with `SlabBox::leak` are not dropped.
+    pub fn reset(&mut self) {
+        *self.free.get_mut() = FreeList { head: None, live: 0 };
+        self.arena.reset();
+    }
+
+    /// puts `slot` back into the free list, the value has to be dropped or moved out before.
+    fn free(&self, slot: NonNull<Slot<T>>) {
+        let mut free = self.free.lock();
+        unsafe { slot.write(Slot { next: free.head }) };
+        free.head = Some(slot);
+        free.live -= 1;
+    }
+}
+
+// the values are only accessed through the `SlabBox` they were allocated as.
+unsafe impl<T: Send> Send for SlabArena<T> {}
+unsafe impl<T: Send> Sync for SlabArena<T> {}
+
+impl<T> fmt::Debug for SlabArena<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SlabArena")
+            .field("len", &self.len())
+            .field("capacity", &self.capacity())
+            .finish()
+    }
+}
+
+/// object allocated from a `SlabArena`, which is dropped and handed back to the free list on drop.
+pub struct SlabBox<'a, T> {
+    slot: NonNull<Slot<T>>,
+    slab: &'a SlabArena<T>,
+    _value: PhantomData<T>,
+}
+
+impl<'a, T> SlabBox<'a, T> {
+    /// moves the value out of the slab arena and frees its slot.
+    pub fn into_inner(this: Self) -> T {
+        let this = ManuallyDrop::new(this);
+        let value = unsafe { ManuallyDrop::take(&mut (*this.slot.as_ptr()).value) };
+        this.slab.free(this.slot);
+        value
+    }
+
+    /// returns a reference to the value that lives as long as the slab arena is borrowed.
+    /// the slot is only reused after `SlabArena::reset` and the value is not dropped.
+    pub fn leak(this: Self) -> &'a mut T {
+        let this = ManuallyDrop::new(this);
+        unsafe { &mut (*this.slot.as_ptr()).value }
+    }
+}
+
+impl<T> Deref for SlabBox<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { &self.slot.as_ref().value }
+    }
+}
+
+impl<T> DerefMut for SlabBox<'_, T> {
+    fn deref_mut(&mut self) -> &mut T {
+        unsafe { &mut self.slot.as_mut().value }
+    }
+}
+
+impl<T> Drop for SlabBox<'_, T> {
+    fn drop(&mut self) {
+        unsafe { ManuallyDrop::drop(&mut self.slot.as_mut().value) };
+        self.slab.free(self.slot);
+    }
+}
+
+unsafe impl<T: Send> Send for SlabBox<'_, T> {}
+unsafe impl<T: Sync> Sync for SlabBox<'_, T> {}
+
+impl<T: fmt::Debug> fmt::Debug for SlabBox<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use alloc::rc::Rc;
+
+    use super::*;
+
+    #[test]
+    fn reuses_freed_slots() {
+        let slab = SlabArena::new(2);
+        assert_eq!(slab.capacity(), 2);
+
+        let first = slab.alloc(1u64);
+        let second = slab.alloc(2u64);
+        assert!(slab.try_alloc(3).is_err());
+        assert_eq!((*first, *second, slab.len()), (1, 2, 2));
+
+        // freed slots are reused last in, first out
+        let address = NonNull::from(&*second);
+        drop(second);
+        let third = slab.alloc(3);
+        assert_eq!(NonNull::from(&*third), address);
+        assert_eq!(SlabBox::into_inner(third), 3);
+        assert_eq!(slab.len(), 1);
+
+        // churn never exhausts the arena
+        for value in 0..100 {
+            assert_eq!(*slab.alloc(value), value);
+        }
+        assert_eq!(slab.len(), 1);
+    }
+
+    #[test]
+    fn drops_values_on_free() {
+        let tracker = Rc::new(());
+        let mut slab = SlabArena::new(4);
+        let value = slab.alloc(tracker.clone());
+        assert_eq!(Rc::strong_count(&tracker), 2);
+        drop(value);
+        assert_eq!(Rc::strong_count(&tracker), 1);
+
+        // leaked values stay valid until the slab is reset
+        let leaked = SlabBox::leak(slab.alloc(tracker.clone()));
+        assert_eq!(slab.len(), 1);
+        drop(unsafe { core::ptr::read(leaked) });
+        slab.reset();
+        assert!(slab.is_empty());
+        assert_eq!(Rc::strong_count(&tracker), 1);
+    }
+
+    #[test]
+    #[should_panic = "slab is too large"]
+    fn rejects_overflowing_capacities() {
+        SlabArena::<u64>::new(usize::MAX / 4);
+    }
+}
@@ -0,0 +1,185 @@
+//! snapshots of the used part of an arena, so data structures whose blocks refer to each other by offset can be
+//! built once, written to a file and loaded instantly in later runs, e.g. for cache files.
+//! a snapshot is the used front of the memory pool followed by a trailer of `MAGIC`, the used bytes and the
+//! capacity, each as little endian `u64`.
+
+use core::{slice, sync::atomic::Ordering};
+use std::io::{self, Write};
+#[cfg(all(any(unix, windows), feature = "std"))]
+use std::{
+    fs::File,
+    io::{Read, Seek, SeekFrom},
+    path::Path,
+};
+
+#[cfg(all(unix, feature = "std"))]
+use crate::sys;
+use crate::Arena;
+
+/// marks the trailer of a snapshot written by `Arena::snapshot`.
+const MAGIC: [u8; 8] = *b"XSARENA1";
+const TRAILER_LEN: usize = MAGIC.len() + 2 * size_of::<u64>();
+
+impl Arena {
+    /// writes the used front of the memory pool to `writer`, so it can be restored with `Arena::open_snapshot`.
+    /// allocations from the back and the destructors registered with `alloc_droppable` are not part of it.
+    /// pointers are only valid in the restored arena if they are stored as offsets relative to `base`.
+    /// the restored memory pool is page aligned, so blocks stay aligned if the memory pool of this arena is aligned
+    /// to the largest alignment that was allocated, e.g. with `ArenaBuilder::align`.
+    ///
+    /// # Safety
+    /// must not be called while any &mut to the memory pool exist, the same as `content_eq`.
+    /// for arenas created from uninitialized memory every used byte must have been written, including the padding
+    /// that was skipped to align allocations.
+    pub unsafe fn snapshot(&self, writer: &mut impl Write) -> io::Result<()> {
+        let used = self.offset.load(Ordering::Acquire);
+        #[cfg(feature = "asan")]
+        crate::asan::unpoison(self.mem_pool.as_mut_ptr(), used);
+
+        writer.write_all(unsafe { slice::from_raw_parts(self.mem_pool.as_mut_ptr(), used) })?;
+        writer.write_all(&MAGIC)?;
+        writer.write_all(&(used as u64).to_le_bytes())?;
+        writer.write_all(&(self.requested_capacity.max(used) as u64).to_le_bytes())
+    }
+
+    /// creates a mapped arena with the capacity of the snapshot at `path` and restores its contents.
+    /// on unix the file is mapped copy-on-write, so only the pages that are accessed are read and changes are
+    /// never written back. elsewhere the contents are read up front.
+    /// the restored bytes are treated as if they were allocated before a mark, so the arena only discards them on
+    /// `reset` and new allocations are placed after them.
+    #[cfg(all(any(unix, windows), feature = "std"))]
+    pub fn open_snapshot(path: impl AsRef<Path>) -> io::Result<Self> {
+        let mut file = File::open(path)?;
+        let (used, capacity) = read_trailer(&mut file)?;
+
+        let mut arena = Arena::new_mmap(capacity)?;
+        if used > 0 {
+            let base = arena.mem_pool.as_mut_ptr();
+            #[cfg(unix)]
+            unsafe {
+                sys::map_file(base, used, &file)?
+            };
+            #[cfg(not(unix))]
+            {
+                file.seek(SeekFrom::Start(0))?;
+                file.read_exact(unsafe { slice::from_raw_parts_mut(base, used) })?;
+            }
+            #[cfg(feature = "asan")]
+            crate::asan::unpoison(base, used);
+        }
+
+        *arena.offset.get_mut() = used;
+        *arena.frozen.get_mut() = used;
+        arena.stats.peak_offset.fetch_max(used, Ordering::Relaxed);
+        Ok(arena)
+    }
+}
+
+/// returns the used bytes and the capacity stored in the trailer of a snapshot.
+#[cfg(all(any(unix, windows), feature = "std"))]
+fn read_trailer(file: &mut File) -> io::Result<(usize, usize)> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "not an arena snapshot");
+
+    let len = file.metadata()?.len();
+    if len < TRAILER_LEN as u64 {
+        return Err(invalid());
+    }
+    let mut trailer = [0u8; TRAILER_LEN];
+    file.seek(SeekFrom::End(-(TRAILER_LEN as i64)))?;
+    file.read_exact(&mut trailer)?;
+
+    let field = |index: usize| {
+        let start = MAGIC.len() + index * size_of::<u64>();
+        u64::from_le_bytes(trailer[start..start + size_of::<u64>()].try_into().unwrap())
+    };
+    let (used, capacity) = (field(0), field(1));
+    if trailer[..MAGIC.len()] != MAGIC || used + TRAILER_LEN as u64 != len || capacity < used {
+        return Err(invalid());
+    }
+    Ok((
+        usize::try_from(used).map_err(|_| invalid())?,
+        usize::try_from(capacity).map_err(|_| invalid())?,
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use core::alloc::Layout;
+
+    use super::*;
+
+    /// node of a linked list that refers to the next node by its offset in the arena.
+    #[derive(Clone, Copy)]
+    #[repr(C)]
+    struct Node {
+        value: u64,
+        next: usize,
+    }
+
+    #[test]
+    fn snapshot_layout() {
+        let arena = Arena::new(64);
+        let (block, _) = arena.allocate_with_offset(Layout::new::<[u8; 4]>()).unwrap();
+        unsafe { block.as_mut_ptr().copy_from_nonoverlapping([1, 2, 3, 4].as_ptr(), 4) };
+
+        let mut bytes = Vec::new();
+        unsafe { arena.snapshot(&mut bytes) }.unwrap();
+        assert_eq!(bytes.len(), 4 + TRAILER_LEN);
+        assert_eq!(bytes[..4], [1, 2, 3, 4]);
+        assert_eq!(bytes[4..12], MAGIC);
+        assert_eq!(bytes[12..20], 4u64.to_le_bytes());
+        assert_eq!(bytes[20..], 64u64.to_le_bytes());
+    }
+
+    #[cfg(all(any(unix, windows), feature = "std"))]
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn restores_offset_based_structures() {
+        let path = std::env::temp_dir().join(format!("xs_arena_snapshot_{}", std::process::id()));
+        {
+            // the padding is written to the snapshot as well, so it has to be initialized.
+            let arena = Arena::builder().capacity(4096).align(8).zeroed(true).build().unwrap().into_fixed().unwrap();
+            let mut next = usize::MAX;
+            for value in 0..4 {
+                let (block, offset) = arena.allocate_with_offset(Layout::new::<Node>()).unwrap();
+                unsafe { block.as_mut_ptr().cast::<Node>().write(Node { value, next }) };
+                next = offset;
+            }
+            let head = arena.get_next_mem_slice(Layout::new::<usize>()).unwrap();
+            unsafe { head.as_mut_ptr().cast::<usize>().write_unaligned(next) };
+            unsafe { arena.snapshot(&mut File::create(&path).unwrap()) }.unwrap();
+        }
+
+        let arena = Arena::open_snapshot(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(arena.capacity(), 4096);
+
+        let head_offset = arena.used_space() - size_of::<usize>();
+        let mut next = unsafe { arena.base().add(head_offset).cast::<usize>().read_unaligned() };
+        let mut values = Vec::new();
+        while next != usize::MAX {
+            let node = unsafe { arena.base().add(next).cast::<Node>().read() };
+            values.push(node.value);
+            next = node.next;
+        }
+        assert_eq!(values, [3, 2, 1, 0]);
+
+        // the restored bytes aren't reclaimed when new allocations are freed
+        let used = arena.used_space();
+        let block = arena.get_next_mem_slice(Layout::new::<u64>()).unwrap();
+        unsafe { block.as_mut_ptr().write_bytes(0xFF, 8) };
+        unsafe { arena.free_last(block.as_non_null_ptr(), Layout::new::<u64>()) };
+        assert_eq!(arena.used_space(), used);
+    }
+
+    #[cfg(all(any(unix, windows), feature = "std"))]
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn rejects_other_files() {
+        let path = std::env::temp_dir().join(format!("xs_arena_not_a_snapshot_{}", std::process::id()));
+        std::fs::write(&path, [0u8; 64]).unwrap();
+        let err = Arena::open_snapshot(&path).err().unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,147 @@
+use alloc::vec::Vec;
+use core::{
+    alloc::Layout,
+    marker::PhantomData,
+    ops::Deref,
+    ptr::NonNull,
+};
+
+use crate::{Arena, ArenaError, ArenaErrorKind, Handle};
+
+/// alignment of the start of every sub-arena created by `Arena::split`.
+const SPLIT_ALIGN: usize = 16;
+
+impl Arena {
+    /// carves one block with the passed size per entry in `sizes` out of the arena and returns an independent arena on
+    /// top of each, e.g. to partition one large up-front reservation into per-subsystem or per-thread regions.
+    /// every sub-arena has its own offset and can be reset on its own, they start aligned to 16 bytes.
+    /// the blocks stay allocated in this arena, it can't be reset while the sub-arenas are borrowing it.
+    /// sub-arenas use the rounding and leak policy of this arena.
+    pub fn split(&self, sizes: &[usize]) -> Result<Vec<SubArena<'_>>, ArenaError> {
+        let overflow = |size| ArenaError {
+            layout: Layout::array::<u8>(size).unwrap_or(Layout::new::<u8>()),
+            available: self.available_space(),
+            kind: ArenaErrorKind::Overflow,
+        };
+
+        let mut starts = Vec::with_capacity(sizes.len());
+        let mut total = 0usize;
+        for &size in sizes {
+            total = total
+                .checked_next_multiple_of(SPLIT_ALIGN)
+                .ok_or_else(|| overflow(usize::MAX))?;
+            starts.push(total);
+            total = total.checked_add(size).ok_or_else(|| overflow(size))?;
+        }
+        let layout = Layout::from_size_align(total, SPLIT_ALIGN).map_err(|_| overflow(total))?;
+        let block = self.try_allocate(layout)?.as_non_null_ptr();
+
+        Ok(starts
+            .into_iter()
+            .zip(sizes)
+            .map(|(start, &size)| {
+                let mem = NonNull::slice_from_raw_parts(unsafe { block.add(start) }, size);
+                let mut arena = unsafe { Arena::from_external(mem) };
+                arena.rounding = self.rounding;
+                arena.leak_policy = self.leak_policy;
+                SubArena {
+                    arena,
+                    _parent: PhantomData,
+                }
+            })
+            .collect())
+    }
+}
+
+/// arena on top of a block of another arena, created by `Arena::split`.
+/// it only hands out `&Arena`, since moving the arena out would let it outlive the block of the parent.
+///
+/// ```compile_fail
+/// let arena = xs_arena_allocator::Arena::new(64);
+/// let mut parts = arena.split(&[32]).unwrap();
+/// std::mem::swap(&mut *parts[0], &mut xs_arena_allocator::Arena::new(1));
+/// ```
+pub struct SubArena<'a> {
+    arena: Arena,
+    _parent: PhantomData<&'a Arena>,
+}
+
+impl SubArena<'_> {
+    /// see `Arena::reset`.
+    pub fn reset(&mut self) {
+        self.arena.reset();
+    }
+
+    /// see `Arena::scope`.
+    pub fn scope<R>(&mut self, f: impl FnOnce(&Arena) -> R) -> R {
+        self.arena.scope(f)
+    }
+
+    /// see `Arena::get_mut`.
+    pub fn get_mut<T>(&mut self, handle: Handle<T>) -> Option<&mut T> {
+        self.arena.get_mut(handle)
+    }
+}
+
+impl Deref for SubArena<'_> {
+    type Target = Arena;
+
+    fn deref(&self) -> &Self::Target {
+        &self.arena
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn independent_sub_arenas() {
+        let arena = Arena::new(1024);
+        arena.get_next_mem_slice(Layout::new::<u8>()).unwrap();
+
+        let mut parts = arena.split(&[100, 200, 50]).unwrap();
+        assert_eq!(
+            parts.iter().map(|part| part.capacity()).collect::<Vec<_>>(),
+            [100, 200, 50]
+        );
+        assert!(parts.iter().all(|part| part.base().as_ptr().addr() % SPLIT_ALIGN == 0));
+        assert!(arena.used_space() >= 1 + 100 + 200 + 50);
+
+        // every part is used by its own thread and exhausted on its own
+        std::thread::scope(|scope| {
+            for part in &parts {
+                scope.spawn(move || {
+                    let block = part
+                        .get_next_mem_slice(Layout::array::<u8>(part.capacity()).unwrap())
+                        .unwrap();
+                    unsafe { block.as_mut_ptr().write_bytes(0xAB, block.len()) };
+                    assert!(part.get_next_mem_slice(Layout::new::<u8>()).is_err());
+                });
+            }
+        });
+
+        parts[1].reset();
+        assert_eq!(parts[1].available_space(), 200);
+        assert_eq!(parts[0].available_space(), 0);
+
+        parts[2].reset();
+        let handle = parts[2].insert(1u32);
+        *parts[2].get_mut(handle).unwrap() += 1;
+        let sum = parts[2].scope(|part| *part.alloc(2u32) + part.get(handle).unwrap());
+        assert_eq!(sum, 4);
+        assert_eq!(parts[2].used_space(), 4);
+    }
+
+    #[test]
+    fn rejects_oversized_splits() {
+        let arena = Arena::new(64);
+        assert_eq!(arena.split(&[32, 48]).err().unwrap().kind, ArenaErrorKind::OutOfSpace);
+        assert_eq!(
+            arena.split(&[8, usize::MAX]).err().unwrap().kind,
+            ArenaErrorKind::Overflow
+        );
+        assert_eq!(arena.used_space(), 0);
+        assert!(arena.split(&[]).unwrap().is_empty());
+    }
+}
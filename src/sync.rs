@@ -0,0 +1,198 @@
+//! locking primitives that work with and without the standard library.
+//! the atomics of the arena are replaced by the ones of loom when building with `RUSTFLAGS="--cfg loom"`,
+//! so the lock-free allocation path can be model checked.
+
+#[cfg(feature = "std")]
+pub(crate) use self::std_mutex::Mutex;
+#[cfg(not(feature = "std"))]
+pub(crate) use self::spin_mutex::Mutex;
+
+#[cfg(feature = "std")]
+mod std_mutex {
+    use std::sync::{MutexGuard, PoisonError};
+
+    /// `std::sync::Mutex` that ignores poisoning, since the protected data stays consistent on panics.
+    #[derive(Default)]
+    pub(crate) struct Mutex<T>(std::sync::Mutex<T>);
+
+    impl<T> Mutex<T> {
+        pub(crate) const fn new(value: T) -> Self {
+            Self(std::sync::Mutex::new(value))
+        }
+
+        pub(crate) fn lock(&self) -> MutexGuard<'_, T> {
+            self.0.lock().unwrap_or_else(PoisonError::into_inner)
+        }
+
+        pub(crate) fn get_mut(&mut self) -> &mut T {
+            self.0.get_mut().unwrap_or_else(PoisonError::into_inner)
+        }
+    }
+}
+
+#[cfg(not(feature = "std"))]
+mod spin_mutex {
+    use core::{
+        cell::UnsafeCell,
+        hint,
+        ops::{Deref, DerefMut},
+        sync::atomic::{AtomicBool, Ordering},
+    };
+
+    /// minimal spin lock for targets without an operating system.
+    #[derive(Default)]
+    pub(crate) struct Mutex<T> {
+        locked: AtomicBool,
+        value: UnsafeCell<T>,
+    }
+
+    unsafe impl<T: Send> Send for Mutex<T> {}
+    unsafe impl<T: Send> Sync for Mutex<T> {}
+
+    impl<T> Mutex<T> {
+        pub(crate) const fn new(value: T) -> Self {
+            Self {
+                locked: AtomicBool::new(false),
+                value: UnsafeCell::new(value),
+            }
+        }
+
+        pub(crate) fn lock(&self) -> MutexGuard<'_, T> {
+            while self
+                .locked
+                .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+                .is_err()
+            {
+                hint::spin_loop();
+            }
+            MutexGuard { mutex: self }
+        }
+
+        pub(crate) fn get_mut(&mut self) -> &mut T {
+            self.value.get_mut()
+        }
+    }
+
+    pub(crate) struct MutexGuard<'a, T> {
+        mutex: &'a Mutex<T>,
+    }
+
+    impl<T> Deref for MutexGuard<'_, T> {
+        type Target = T;
+
+        fn deref(&self) -> &T {
+            unsafe { &*self.mutex.value.get() }
+        }
+    }
+
+    impl<T> DerefMut for MutexGuard<'_, T> {
+        fn deref_mut(&mut self) -> &mut T {
+            unsafe { &mut *self.mutex.value.get() }
+        }
+    }
+
+    impl<T> Drop for MutexGuard<'_, T> {
+        fn drop(&mut self) {
+            self.mutex.locked.store(false, Ordering::Release);
+        }
+    }
+}
+
+/// aligns a value to its own cache line, so writes to it don't slow down the accesses to the fields next to it.
+/// x86_64 and aarch64 prefetch pairs of lines, so two lines are used there.
+#[cfg_attr(any(target_arch = "x86_64", target_arch = "aarch64"), repr(align(128)))]
+#[cfg_attr(not(any(target_arch = "x86_64", target_arch = "aarch64")), repr(align(64)))]
+#[derive(Default)]
+pub(crate) struct CachePadded<T>(pub(crate) T);
+
+impl<T> core::ops::Deref for CachePadded<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> core::ops::DerefMut for CachePadded<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+/// atomics used by `Arena`.
+pub(crate) mod atomic {
+    pub(crate) use core::sync::atomic::Ordering;
+    #[cfg(not(loom))]
+    pub(crate) use core::sync::atomic::{AtomicPtr, AtomicU8, AtomicUsize};
+
+    #[cfg(loom)]
+    pub(crate) use self::loom_atomic::{AtomicPtr, AtomicU8, AtomicUsize};
+
+    #[cfg(loom)]
+    mod loom_atomic {
+        use core::ops::{Deref, DerefMut};
+
+        /// wraps a loom atomic and adds `get_mut`, which loom only offers as `with_mut`.
+        macro_rules! atomic {
+            ($name:ident, $value:ty $(, $param:ident)?) => {
+                #[derive(Default)]
+                pub(crate) struct $name$(<$param>)?(loom::sync::atomic::$name$(<$param>)?);
+
+                impl$(<$param>)? $name$(<$param>)? {
+                    pub(crate) fn new(value: $value) -> Self {
+                        Self(loom::sync::atomic::$name::new(value))
+                    }
+
+                    /// the value is read when the guard is created and written back when it is dropped.
+                    #[allow(dead_code)]
+                    pub(crate) fn get_mut(&mut self) -> GetMut<'_, $value, loom::sync::atomic::$name$(<$param>)?> {
+                        let value = unsafe { self.0.unsync_load() };
+                        GetMut {
+                            atomic: &mut self.0,
+                            value,
+                            store: |atomic, value| atomic.with_mut(|current| *current = value),
+                        }
+                    }
+                }
+
+                impl$(<$param>)? Deref for $name$(<$param>)? {
+                    type Target = loom::sync::atomic::$name$(<$param>)?;
+
+                    fn deref(&self) -> &Self::Target {
+                        &self.0
+                    }
+                }
+            };
+        }
+
+        atomic!(AtomicUsize, usize);
+        atomic!(AtomicU8, u8);
+        atomic!(AtomicPtr, *mut T, T);
+
+        pub(crate) struct GetMut<'a, V: Copy, A> {
+            atomic: &'a mut A,
+            value: V,
+            store: fn(&mut A, V),
+        }
+
+        impl<V: Copy, A> Deref for GetMut<'_, V, A> {
+            type Target = V;
+
+            fn deref(&self) -> &V {
+                &self.value
+            }
+        }
+
+        impl<V: Copy, A> DerefMut for GetMut<'_, V, A> {
+            fn deref_mut(&mut self) -> &mut V {
+                &mut self.value
+            }
+        }
+
+        impl<V: Copy, A> Drop for GetMut<'_, V, A> {
+            fn drop(&mut self) {
+                (self.store)(self.atomic, self.value);
+            }
+        }
+    }
+}
@@ -0,0 +1,11 @@
+//! thin wrappers around the virtual memory functions of the operating system.
+
+#[cfg(unix)]
+mod unix;
+#[cfg(windows)]
+mod windows;
+
+#[cfg(unix)]
+pub use unix::*;
+#[cfg(windows)]
+pub use windows::*;
@@ -0,0 +1,303 @@
+//! virtual memory functions of unix systems, built on `mmap`.
+
+use crate::NumaPolicy;
+use std::{
+    fs::File,
+    io,
+    os::fd::AsRawFd,
+    ptr::{self, NonNull},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+// the values of the flags differ between systems and architectures, so they are taken from libc.
+use libc::{
+    madvise, mlock, mmap, mprotect, munlock, munmap, sysconf, MADV_DONTNEED, MAP_ANONYMOUS, MAP_FIXED, MAP_PRIVATE,
+    PROT_NONE, PROT_READ, PROT_WRITE, _SC_PAGESIZE,
+};
+#[cfg(not(any(target_os = "freebsd", target_os = "dragonfly")))]
+use libc::MAP_NORESERVE;
+#[cfg(target_os = "linux")]
+use libc::{syscall, MADV_DONTDUMP, MADV_HUGEPAGE, SYS_mbind};
+
+/// freebsd never reserves swap for private mappings, so it has no flag to opt out of it.
+#[cfg(any(target_os = "freebsd", target_os = "dragonfly"))]
+const MAP_NORESERVE: libc::c_int = 0;
+
+/// the memory policies of `mbind`, which libc doesn't define.
+#[cfg(target_os = "linux")]
+const MPOL_BIND: libc::c_int = 2;
+#[cfg(target_os = "linux")]
+const MPOL_INTERLEAVE: libc::c_int = 3;
+
+/// amount of nodes that fit into the node mask passed to `mbind`.
+#[cfg(target_os = "linux")]
+const MAX_NUMA_NODES: usize = 1024;
+
+/// size of the transparent huge pages the memory pool is aligned to.
+pub const HUGE_PAGE_SIZE: usize = 2 * 1024 * 1024;
+
+/// returns the page size of the system.
+pub fn page_size() -> usize {
+    static PAGE_SIZE: AtomicUsize = AtomicUsize::new(0);
+
+    let mut page_size = PAGE_SIZE.load(Ordering::Relaxed);
+    if page_size == 0 {
+        page_size = unsafe { sysconf(_SC_PAGESIZE) } as usize;
+        PAGE_SIZE.store(page_size, Ordering::Relaxed);
+    }
+    page_size
+}
+
+/// reserves `len` bytes of address space without backing them by physical memory.
+/// if `accessible` is false the pages have to be committed with `commit` before they can be used.
+/// otherwise the operating system commits pages lazily on first access.
+pub fn reserve(len: usize, accessible: bool) -> io::Result<NonNull<u8>> {
+    let prot = if accessible { PROT_READ | PROT_WRITE } else { PROT_NONE };
+    let ptr = unsafe {
+        mmap(
+            ptr::null_mut(),
+            len,
+            prot,
+            MAP_PRIVATE | MAP_ANONYMOUS | MAP_NORESERVE,
+            -1,
+            0,
+        )
+    };
+
+    if ptr.addr() == usize::MAX {
+        return Err(io::Error::last_os_error());
+    }
+
+    Ok(unsafe { NonNull::new_unchecked(ptr.cast()) })
+}
+
+/// same as `reserve` but the returned address is a multiple of `align` and is surrounded by `guard` bytes
+/// of inaccessible address space on both sides, so that accesses right before or after the range fault.
+/// this over-reserves by `align` bytes and unmaps the unaligned head and the remaining tail again.
+pub fn reserve_aligned(len: usize, align: usize, guard: usize, accessible: bool) -> io::Result<NonNull<u8>> {
+    let align = align.max(page_size());
+    let padding = if align > page_size() { align } else { 0 };
+    let total = len
+        .checked_add(2 * guard)
+        .and_then(|total| total.checked_add(padding))
+        .ok_or(io::ErrorKind::InvalidInput)?;
+    let ptr = reserve(total, accessible)?.as_ptr();
+    let head = (ptr.addr() + guard).next_multiple_of(align) - ptr.addr() - guard;
+    let tail = total - head - len - 2 * guard;
+
+    unsafe {
+        if head > 0 {
+            release(ptr, head);
+        }
+        if tail > 0 {
+            release(ptr.add(total - tail), tail);
+        }
+
+        let base = ptr.add(head + guard);
+        if guard > 0 && accessible {
+            let guarded = protect(base.sub(guard), guard).and_then(|_| protect(base.add(len), guard));
+            if let Err(err) = guarded {
+                release(base.sub(guard), len + 2 * guard);
+                return Err(err);
+            }
+        }
+        Ok(NonNull::new_unchecked(base))
+    }
+}
+
+/// makes the pages in the passed range readable and writable.
+///
+/// # Safety
+/// the range must be part of a mapping created by `reserve`.
+pub unsafe fn commit(ptr: *mut u8, len: usize) -> io::Result<()> {
+    if unsafe { mprotect(ptr.cast(), len, PROT_READ | PROT_WRITE) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// gives the physical memory behind the pages in the passed range back and makes them inaccessible again.
+/// the pages can be made accessible again with `commit` and are zeroed then.
+///
+/// # Safety
+/// the range must be part of a mapping created by `reserve` and must not be accessed until it is committed again.
+#[cfg(target_os = "linux")]
+pub unsafe fn decommit(ptr: *mut u8, len: usize) -> io::Result<()> {
+    // the mapping is kept, so its NUMA policy and huge page advice still apply once the pages are committed again.
+    if unsafe { madvise(ptr.cast(), len, MADV_DONTNEED) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    unsafe { protect(ptr, len) }
+}
+
+/// gives the physical memory behind the pages in the passed range back and makes them inaccessible again.
+/// the pages can be made accessible again with `commit` and are zeroed then.
+///
+/// # Safety
+/// the range must be part of a mapping created by `reserve` and must not be accessed until it is committed again.
+#[cfg(not(target_os = "linux"))]
+pub unsafe fn decommit(ptr: *mut u8, len: usize) -> io::Result<()> {
+    // `MADV_DONTNEED` keeps the contents of the pages on other systems, mapping fresh pages over them zeroes them.
+    let flags = MAP_PRIVATE | MAP_ANONYMOUS | MAP_NORESERVE | MAP_FIXED;
+    if unsafe { mmap(ptr.cast(), len, PROT_NONE, flags, -1, 0) }.addr() == usize::MAX {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// gives the physical memory behind the pages in the passed range back while keeping them accessible.
+/// on linux the pages read as zero afterwards, other systems may keep their contents until they are reclaimed.
+///
+/// # Safety
+/// the range must be part of a mapping created by `reserve` and its contents must not be needed anymore.
+pub unsafe fn discard(ptr: *mut u8, len: usize) -> io::Result<()> {
+    if unsafe { madvise(ptr.cast(), len, MADV_DONTNEED) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// maps the first `len` bytes of `file` copy-on-write over the passed range, so the contents are read lazily on
+/// first access and changes are never written back. `len` is rounded up to the page size, the part of the last
+/// page past the end of the file reads as zero.
+///
+/// # Safety
+/// `ptr` must be page aligned and the rounded range must be part of a mapping created by `reserve`.
+/// the file must be at least as large as `len`.
+pub unsafe fn map_file(ptr: *mut u8, len: usize, file: &File) -> io::Result<()> {
+    let flags = MAP_PRIVATE | MAP_FIXED;
+    let prot = PROT_READ | PROT_WRITE;
+    if unsafe { mmap(ptr.cast(), len, prot, flags, file.as_raw_fd(), 0) }.addr() == usize::MAX {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// makes the pages in the passed range inaccessible, so that every access faults.
+///
+/// # Safety
+/// the range must be part of a mapping created by `reserve` and must not be accessed afterwards.
+pub unsafe fn protect(ptr: *mut u8, len: usize) -> io::Result<()> {
+    if unsafe { mprotect(ptr.cast(), len, PROT_NONE) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// locks the pages in the passed range into memory, so they are never written to swap.
+///
+/// # Safety
+/// the range must be part of a mapping created by `reserve`.
+pub unsafe fn lock(ptr: *mut u8, len: usize) -> io::Result<()> {
+    if unsafe { mlock(ptr.cast(), len) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// undoes `lock`.
+///
+/// # Safety
+/// the range must be part of a mapping created by `reserve`.
+pub unsafe fn unlock(ptr: *mut u8, len: usize) {
+    unsafe {
+        munlock(ptr.cast(), len);
+    }
+}
+
+/// excludes the pages in the passed range from core dumps.
+///
+/// # Safety
+/// the range must be part of a mapping created by `reserve`.
+#[cfg(target_os = "linux")]
+pub unsafe fn exclude_from_dumps(ptr: *mut u8, len: usize) -> io::Result<()> {
+    if unsafe { madvise(ptr.cast(), len, MADV_DONTDUMP) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// excluding pages from core dumps is only supported on linux.
+///
+/// # Safety
+/// always safe to call, the signature matches the linux version.
+#[cfg(not(target_os = "linux"))]
+pub unsafe fn exclude_from_dumps(_ptr: *mut u8, _len: usize) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+/// asks the operating system to back the passed range with transparent huge pages.
+///
+/// # Safety
+/// the range must be part of a mapping created by `reserve`.
+#[cfg(target_os = "linux")]
+pub unsafe fn advise_huge_pages(ptr: *mut u8, len: usize) -> io::Result<()> {
+    if unsafe { madvise(ptr.cast(), len, MADV_HUGEPAGE) } != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// transparent huge pages are only supported on linux.
+///
+/// # Safety
+/// always safe to call, the signature matches the linux version.
+#[cfg(not(target_os = "linux"))]
+pub unsafe fn advise_huge_pages(_ptr: *mut u8, _len: usize) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+/// places the pages in the passed range on the NUMA nodes selected by `policy` once they are touched.
+///
+/// # Safety
+/// the range must be part of a mapping created by `reserve`.
+#[cfg(target_os = "linux")]
+pub unsafe fn set_numa_policy(ptr: *mut u8, len: usize, policy: NumaPolicy) -> io::Result<()> {
+    let mut nodes = [0u64; MAX_NUMA_NODES / 64];
+    let mode = match policy {
+        NumaPolicy::Node(node) if node < MAX_NUMA_NODES => {
+            nodes[node / 64] = 1 << (node % 64);
+            MPOL_BIND
+        }
+        NumaPolicy::Node(_) => return Err(io::ErrorKind::InvalidInput.into()),
+        // the kernel only interleaves over the nodes the process is allowed to use.
+        NumaPolicy::Interleave => {
+            nodes.fill(u64::MAX);
+            MPOL_INTERLEAVE
+        }
+    };
+
+    // the kernel ignores the last bit of the mask.
+    let result = unsafe { syscall(SYS_mbind, ptr, len, mode, nodes.as_ptr(), MAX_NUMA_NODES + 1, 0) };
+    if result != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// NUMA placement is only supported on linux.
+///
+/// # Safety
+/// always safe to call, the signature matches the linux version.
+#[cfg(not(target_os = "linux"))]
+pub unsafe fn set_numa_policy(_ptr: *mut u8, _len: usize, _policy: NumaPolicy) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+/// unmaps the passed range.
+///
+/// # Safety
+/// the range must be part of a mapping created by `reserve` and must not be accessed afterwards.
+pub unsafe fn release(ptr: *mut u8, len: usize) {
+    unsafe {
+        munmap(ptr.cast(), len);
+    }
+}
+
+/// unmaps the tail of a mapping, keeping the start of it mapped.
+///
+/// # Safety
+/// same as `release`.
+pub unsafe fn release_tail(ptr: *mut u8, len: usize) {
+    unsafe { release(ptr, len) };
+}
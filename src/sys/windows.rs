@@ -0,0 +1,189 @@
+//! virtual memory functions of windows, built on `VirtualAlloc`.
+//! address space is reserved with `MEM_RESERVE` and pages are committed with `MEM_COMMIT` when they are needed.
+
+use crate::NumaPolicy;
+use std::{
+    ffi::c_void,
+    io, mem,
+    ptr::{self, NonNull},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+const MEM_COMMIT: u32 = 0x1000;
+const MEM_RESERVE: u32 = 0x2000;
+const MEM_DECOMMIT: u32 = 0x4000;
+const MEM_RELEASE: u32 = 0x8000;
+const MEM_RESET: u32 = 0x80000;
+const PAGE_NOACCESS: u32 = 0x01;
+const PAGE_READWRITE: u32 = 0x04;
+
+/// size of the large pages the memory pool is aligned to.
+pub const HUGE_PAGE_SIZE: usize = 2 * 1024 * 1024;
+
+// only some of the fields that the operating system fills in are read.
+#[allow(dead_code)]
+#[repr(C)]
+struct SystemInfo {
+    processor_architecture: u16,
+    reserved: u16,
+    page_size: u32,
+    minimum_application_address: *mut c_void,
+    maximum_application_address: *mut c_void,
+    active_processor_mask: usize,
+    number_of_processors: u32,
+    processor_type: u32,
+    allocation_granularity: u32,
+    processor_level: u16,
+    processor_revision: u16,
+}
+
+#[allow(dead_code)]
+#[repr(C)]
+struct MemoryBasicInformation {
+    base_address: *mut c_void,
+    allocation_base: *mut c_void,
+    allocation_protect: u32,
+    #[cfg(target_pointer_width = "64")]
+    partition_id: u16,
+    region_size: usize,
+    state: u32,
+    protect: u32,
+    kind: u32,
+}
+
+#[link(name = "kernel32")]
+extern "system" {
+    fn VirtualAlloc(address: *mut c_void, size: usize, allocation_type: u32, protect: u32) -> *mut c_void;
+    fn VirtualFree(address: *mut c_void, size: usize, free_type: u32) -> i32;
+    fn VirtualQuery(address: *const c_void, buffer: *mut MemoryBasicInformation, length: usize) -> usize;
+    fn GetSystemInfo(system_info: *mut SystemInfo);
+}
+
+/// returns the page size of the system.
+pub fn page_size() -> usize {
+    static PAGE_SIZE: AtomicUsize = AtomicUsize::new(0);
+
+    let mut page_size = PAGE_SIZE.load(Ordering::Relaxed);
+    if page_size == 0 {
+        let mut info = mem::MaybeUninit::<SystemInfo>::uninit();
+        page_size = unsafe {
+            GetSystemInfo(info.as_mut_ptr());
+            info.assume_init().page_size as usize
+        };
+        PAGE_SIZE.store(page_size, Ordering::Relaxed);
+    }
+    page_size
+}
+
+/// reserves `len` bytes of address space whose start is a multiple of `align` and which is surrounded by `guard`
+/// bytes of inaccessible address space on both sides. if `accessible` is true the range is committed right away,
+/// otherwise the pages have to be committed with `commit` before they can be used.
+/// reservations can't be trimmed, so the padding needed for the alignment stays reserved but inaccessible.
+pub fn reserve_aligned(len: usize, align: usize, guard: usize, accessible: bool) -> io::Result<NonNull<u8>> {
+    let align = align.max(page_size());
+    let padding = if align > page_size() { align } else { 0 };
+    let total = len
+        .checked_add(2 * guard)
+        .and_then(|total| total.checked_add(padding))
+        .ok_or(io::ErrorKind::InvalidInput)?;
+
+    let reservation = unsafe { VirtualAlloc(ptr::null_mut(), total, MEM_RESERVE, PAGE_NOACCESS) };
+    if reservation.is_null() {
+        return Err(io::Error::last_os_error());
+    }
+
+    let offset = (reservation.addr() + guard).next_multiple_of(align) - reservation.addr();
+    unsafe {
+        let base = reservation.cast::<u8>().add(offset);
+        if accessible {
+            if let Err(err) = commit(base, len) {
+                release(base, len);
+                return Err(err);
+            }
+        }
+        Ok(NonNull::new_unchecked(base))
+    }
+}
+
+/// commits the pages in the passed range and makes them readable and writable.
+///
+/// # Safety
+/// the range must be part of a reservation created by `reserve_aligned`.
+pub unsafe fn commit(ptr: *mut u8, len: usize) -> io::Result<()> {
+    if unsafe { VirtualAlloc(ptr.cast(), len, MEM_COMMIT, PAGE_READWRITE) }.is_null() {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// gives the physical memory behind the pages in the passed range back and makes them inaccessible again.
+/// the pages can be made accessible again with `commit` and are zeroed then.
+///
+/// # Safety
+/// the range must be part of a reservation created by `reserve_aligned` and must not be accessed until it is
+/// committed again.
+pub unsafe fn decommit(ptr: *mut u8, len: usize) -> io::Result<()> {
+    if unsafe { VirtualFree(ptr.cast(), len, MEM_DECOMMIT) } == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// gives the physical memory behind the pages in the passed range back while keeping them accessible.
+/// the contents of the pages are undefined afterwards.
+///
+/// # Safety
+/// the range must be committed and its contents must not be needed anymore.
+pub unsafe fn discard(ptr: *mut u8, len: usize) -> io::Result<()> {
+    if unsafe { VirtualAlloc(ptr.cast(), len, MEM_RESET, PAGE_READWRITE) }.is_null() {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+/// makes the pages in the passed range inaccessible, so that every access faults.
+/// the pages are decommitted, since reserved pages are never accessible.
+///
+/// # Safety
+/// the range must be part of a reservation created by `reserve_aligned` and must not be accessed afterwards.
+pub unsafe fn protect(ptr: *mut u8, len: usize) -> io::Result<()> {
+    unsafe { decommit(ptr, len) }
+}
+
+/// transparent huge pages are only supported on linux.
+///
+/// # Safety
+/// always safe to call, the signature matches the linux version.
+pub unsafe fn advise_huge_pages(_ptr: *mut u8, _len: usize) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+/// NUMA placement is only supported on linux.
+///
+/// # Safety
+/// always safe to call, the signature matches the linux version.
+pub unsafe fn set_numa_policy(_ptr: *mut u8, _len: usize, _policy: NumaPolicy) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+/// releases the whole reservation that contains `ptr`. reservations can only be released as a whole,
+/// so `len` only exists to match the signature of the unix version.
+///
+/// # Safety
+/// `ptr` must be part of a reservation created by `reserve_aligned`, which must not be accessed afterwards.
+pub unsafe fn release(ptr: *mut u8, _len: usize) {
+    let mut info = mem::MaybeUninit::<MemoryBasicInformation>::uninit();
+    unsafe {
+        if VirtualQuery(ptr.cast(), info.as_mut_ptr(), mem::size_of::<MemoryBasicInformation>()) != 0 {
+            VirtualFree(info.assume_init().allocation_base, 0, MEM_RELEASE);
+        }
+    }
+}
+
+/// decommits the tail of a reservation, which keeps the address space reserved but gives the memory back.
+///
+/// # Safety
+/// the range must be part of a reservation created by `reserve_aligned` and must not be accessed afterwards.
+pub unsafe fn release_tail(ptr: *mut u8, len: usize) {
+    let _ = unsafe { decommit(ptr, len) };
+}
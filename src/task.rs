@@ -0,0 +1,192 @@
+use std::{
+    cell::RefCell,
+    future::Future,
+    pin::Pin,
+    task::{Context, Poll},
+};
+
+use crate::{Arena, ArenaAllocator};
+
+thread_local! {
+    static CURRENT: RefCell<Option<ArenaAllocator>> = const { RefCell::new(None) };
+}
+
+/// future that runs another future with an arena attached, e.g. one arena per request in an async server.
+/// while the inner future is polled the allocator can be retrieved with `ArenaScope::current`, so it doesn't have
+/// to be passed through every call. once the future completes the scope resets the arena if it holds the last
+/// handle to it and every allocation was dropped, otherwise it only gives up its handle.
+/// works with every executor, since the allocator is installed on the polling thread for the duration of each poll.
+pub struct ArenaScope<F> {
+    future: F,
+    allocator: Option<ArenaAllocator>,
+}
+
+impl<F: Future> ArenaScope<F> {
+    /// runs `future` with a new arena of the passed capacity in bytes, which is freed once the future completed and
+    /// every value allocated from it was dropped.
+    pub fn run(capacity: usize, future: F) -> Self {
+        Self::with_allocator(ArenaAllocator::new(Arena::new(capacity)), future)
+    }
+
+    /// runs `future` with `allocator`. a clone of the allocator that is kept outside can reset the arena with
+    /// `ArenaAllocator::try_reset` after the future completed, to reuse it for the next task.
+    pub fn with_allocator(allocator: ArenaAllocator, future: F) -> Self {
+        Self {
+            future,
+            allocator: Some(allocator),
+        }
+    }
+}
+
+impl ArenaScope<()> {
+    /// returns the allocator of the innermost scope that is currently being polled on this thread.
+    pub fn current() -> Option<ArenaAllocator> {
+        CURRENT.with(|current| current.borrow().clone())
+    }
+}
+
+impl<F: Future> Future for ArenaScope<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // the future is structurally pinned, it is never moved out of the scope.
+        let this = unsafe { self.get_unchecked_mut() };
+        let allocator = this.allocator.clone().expect("`ArenaScope` polled after completion");
+
+        let result = {
+            let _current = InstallGuard::install(allocator);
+            unsafe { Pin::new_unchecked(&mut this.future) }.poll(cx)
+        };
+        if result.is_ready() {
+            // the clone of the guard is dropped already, so the scope may hold the last handle.
+            if let Some(mut allocator) = this.allocator.take() {
+                allocator.try_reset();
+            }
+        }
+        result
+    }
+}
+
+/// restores the allocator of the enclosing scope when a poll returns or unwinds.
+struct InstallGuard {
+    previous: Option<ArenaAllocator>,
+}
+
+impl InstallGuard {
+    fn install(allocator: ArenaAllocator) -> Self {
+        Self {
+            previous: CURRENT.with(|current| current.replace(Some(allocator))),
+        }
+    }
+}
+
+impl Drop for InstallGuard {
+    fn drop(&mut self) {
+        CURRENT.with(|current| *current.borrow_mut() = self.previous.take());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        task::Waker,
+    };
+
+    use super::*;
+    use crate::ArenaHooks;
+
+    /// returns pending once, so the scope is polled twice.
+    struct YieldOnce(bool);
+
+    impl Future for YieldOnce {
+        type Output = ();
+
+        fn poll(mut self: Pin<&mut Self>, _: &mut Context<'_>) -> Poll<()> {
+            if self.0 {
+                return Poll::Ready(());
+            }
+            self.0 = true;
+            Poll::Pending
+        }
+    }
+
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = std::pin::pin!(future);
+        let mut cx = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut cx) {
+                return output;
+            }
+        }
+    }
+
+    #[test]
+    fn allocator_is_available_inside_the_task() {
+        let values = block_on(ArenaScope::run(1024, async {
+            let mut values = Vec::new_in(ArenaScope::current().unwrap());
+            values.push(1u32);
+            YieldOnce(false).await;
+            values.push(2);
+
+            // nested scopes shadow the outer one while they are polled
+            let inner = ArenaScope::run(64, async { ArenaScope::current().unwrap().stats().allocations });
+            assert_eq!(inner.await, 0);
+            assert_eq!(ArenaScope::current().unwrap().stats().allocations, 1);
+            values
+        }));
+
+        assert_eq!(values, [1, 2]);
+        assert!(ArenaScope::current().is_none());
+    }
+
+    #[test]
+    fn arena_can_be_reused_after_completion() {
+        let mut allocator = ArenaAllocator::new(Arena::new(1024));
+        let used = block_on(ArenaScope::with_allocator(allocator.clone(), async {
+            let value = Box::new_in(5u64, ArenaScope::current().unwrap());
+            *value
+        }));
+
+        assert_eq!(used, 5);
+        assert!(allocator.try_reset());
+    }
+
+    #[test]
+    fn arena_is_reset_on_completion() {
+        #[derive(Default)]
+        struct Resets(AtomicUsize);
+
+        impl ArenaHooks for Arc<Resets> {
+            fn on_reset(&self) {
+                self.0.fetch_add(1, Ordering::Relaxed);
+            }
+        }
+
+        let resets = Arc::new(Resets::default());
+        let allocator = ArenaAllocator::new(Arena::new(1024)).with_hooks(resets.clone());
+        block_on(ArenaScope::with_allocator(allocator.clone(), async {
+            drop(Box::new_in(1u64, ArenaScope::current().unwrap()));
+        }));
+        // the clone that is kept outside keeps the arena alive
+        assert_eq!(resets.0.load(Ordering::Relaxed), 0);
+
+        // values that outlive the task keep the arena alive as well
+        let value = block_on(ArenaScope::with_allocator(allocator.clone(), async {
+            Box::new_in(2u64, ArenaScope::current().unwrap())
+        }));
+        drop(allocator);
+        assert_eq!(*value, 2);
+        assert_eq!(resets.0.load(Ordering::Relaxed), 0);
+        drop(value);
+
+        let allocator = ArenaAllocator::new(Arena::new(1024)).with_hooks(resets.clone());
+        block_on(ArenaScope::with_allocator(allocator, async {
+            drop(Box::new_in(3u64, ArenaScope::current().unwrap()));
+        }));
+        assert_eq!(resets.0.load(Ordering::Relaxed), 1);
+    }
+}
@@ -0,0 +1,128 @@
+use std::{
+    cell::{Cell, RefCell},
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+use crate::{Arena, DEFAULT_CAPACITY};
+
+static CAPACITY: AtomicUsize = AtomicUsize::new(DEFAULT_CAPACITY);
+
+thread_local! {
+    static ARENA: RefCell<Option<Arena>> = const { RefCell::new(None) };
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+}
+
+/// arena of the current thread, a contention-free scratch allocator for worker threads.
+/// every thread creates its arena on first use and resets it after every use, see `ThreadLocalArena::with`.
+pub struct ThreadLocalArena;
+
+impl ThreadLocalArena {
+    /// sets the capacity of the arena of every thread, defaults to `DEFAULT_CAPACITY`.
+    /// threads that already created an arena replace it the next time they use it.
+    pub fn set_capacity(capacity: usize) {
+        CAPACITY.store(capacity, Ordering::Relaxed);
+    }
+
+    pub fn capacity() -> usize {
+        CAPACITY.load(Ordering::Relaxed)
+    }
+
+    /// runs `f` with the arena of the current thread. nested calls share the arena and it is reset once the
+    /// outermost call returns or unwinds, so nothing allocated inside `f` can outlive it.
+    pub fn with<R>(f: impl FnOnce(&Arena) -> R) -> R {
+        ARENA.with(|cell| {
+            if DEPTH.get() == 0 {
+                let mut arena = cell.borrow_mut();
+                let capacity = Self::capacity();
+                if arena
+                    .as_ref()
+                    .is_none_or(|arena| arena.requested_capacity() != capacity)
+                {
+                    *arena = Some(Arena::new(capacity));
+                }
+            }
+
+            let _reset = ResetGuard::enter(cell);
+            let arena = cell.borrow();
+            f(arena.as_ref().unwrap())
+        })
+    }
+}
+
+/// shorthand for `ThreadLocalArena::with`.
+pub fn with_thread_arena<R>(f: impl FnOnce(&Arena) -> R) -> R {
+    ThreadLocalArena::with(f)
+}
+
+/// resets the arena when the outermost `ThreadLocalArena::with` is left.
+struct ResetGuard<'a> {
+    cell: &'a RefCell<Option<Arena>>,
+}
+
+impl<'a> ResetGuard<'a> {
+    fn enter(cell: &'a RefCell<Option<Arena>>) -> Self {
+        DEPTH.set(DEPTH.get() + 1);
+        Self { cell }
+    }
+}
+
+impl Drop for ResetGuard<'_> {
+    fn drop(&mut self) {
+        DEPTH.set(DEPTH.get() - 1);
+        if DEPTH.get() == 0 {
+            if let Some(arena) = self.cell.borrow_mut().as_mut() {
+                arena.reset();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{alloc::Layout, thread};
+
+    use super::*;
+
+    #[test]
+    fn reset_between_uses() {
+        let used = with_thread_arena(|arena| {
+            let mut values = Vec::new_in(arena);
+            values.extend(0u64..8);
+
+            // nested uses share the arena and don't reset it
+            let nested = with_thread_arena(|nested| {
+                assert!(std::ptr::eq(nested, arena));
+                nested.get_next_mem_slice(Layout::new::<u64>()).unwrap();
+                nested.used_space()
+            });
+            assert_eq!(arena.used_space(), nested);
+            arena.used_space()
+        });
+        assert!(used > 0);
+        assert_eq!(with_thread_arena(|arena| arena.used_space()), 0);
+
+        // the arena is reset when the closure panics as well
+        let panicked = thread::spawn(|| {
+            let _ = std::panic::catch_unwind(|| {
+                with_thread_arena(|arena| {
+                    arena.get_next_mem_slice(Layout::new::<u64>()).unwrap();
+                    panic!("failed while the arena was in use");
+                })
+            });
+            with_thread_arena(|arena| arena.used_space())
+        });
+        assert_eq!(panicked.join().unwrap(), 0);
+    }
+
+    #[test]
+    fn configurable_capacity() {
+        ThreadLocalArena::set_capacity(4096);
+        let capacities = thread::spawn(|| {
+            let before = with_thread_arena(|arena| arena.capacity());
+            ThreadLocalArena::set_capacity(8192);
+            (before, with_thread_arena(|arena| arena.capacity()))
+        });
+        assert_eq!(capacities.join().unwrap(), (4096, 8192));
+        ThreadLocalArena::set_capacity(DEFAULT_CAPACITY);
+    }
+}
@@ -0,0 +1,332 @@
+use core::{
+    alloc::Layout,
+    mem, ptr, slice, str,
+    sync::atomic::Ordering,
+};
+
+use crate::{Arena, ArenaError};
+
+/// destructor of a value allocated with `Arena::alloc_droppable`, stored inside the arena itself.
+pub(crate) struct DropEntry {
+    value: *mut u8,
+    drop_fn: unsafe fn(*mut u8),
+    next: *mut DropEntry,
+}
+
+unsafe fn drop_value<T>(value: *mut u8) {
+    unsafe { ptr::drop_in_place(value.cast::<T>()) }
+}
+
+impl Arena {
+    /// moves `value` into the arena and returns a reference to it that lives as long as the arena is borrowed.
+    /// typed allocations are never handed back, so the arena only reuses their memory on `reset`.
+    /// the value is not dropped.
+    ///
+    /// # Panics
+    /// calls `handle_alloc_error` if the arena is exhausted.
+    #[allow(clippy::mut_from_ref)]
+    #[cfg_attr(feature = "profile", track_caller)]
+    pub fn alloc<T>(&self, value: T) -> &mut T {
+        self.alloc_with(|| value)
+    }
+
+    /// same as `alloc` but creates the value with `f` after the memory was reserved,
+    /// which allows the compiler to construct large values in place.
+    ///
+    /// # Panics
+    /// calls `handle_alloc_error` if the arena is exhausted.
+    #[allow(clippy::mut_from_ref)]
+    #[cfg_attr(feature = "profile", track_caller)]
+    pub fn alloc_with<T>(&self, f: impl FnOnce() -> T) -> &mut T {
+        let slot = self.alloc_layout(Layout::new::<T>()).cast::<T>();
+        unsafe {
+            slot.write(f());
+            &mut *slot
+        }
+    }
+
+    /// same as `alloc` but returns an error instead of panicking if the arena is exhausted.
+    #[allow(clippy::mut_from_ref)]
+    #[cfg_attr(feature = "profile", track_caller)]
+    pub fn try_alloc<T>(&self, value: T) -> Result<&mut T, ArenaError> {
+        let slot = self.try_allocate(Layout::new::<T>())?.as_mut_ptr().cast::<T>();
+        unsafe {
+            slot.write(value);
+            Ok(&mut *slot)
+        }
+    }
+
+    /// copies `values` into the arena.
+    ///
+    /// # Panics
+    /// calls `handle_alloc_error` if the arena is exhausted.
+    #[allow(clippy::mut_from_ref)]
+    #[cfg_attr(feature = "profile", track_caller)]
+    pub fn alloc_slice_copy<T: Copy>(&self, values: &[T]) -> &mut [T] {
+        let slot = self.alloc_array::<T>(values.len());
+        unsafe {
+            ptr::copy_nonoverlapping(values.as_ptr(), slot, values.len());
+            slice::from_raw_parts_mut(slot, values.len())
+        }
+    }
+
+    /// allocates a slice of `len` values, where the value at every index is created by calling `f` with the index.
+    ///
+    /// # Panics
+    /// calls `handle_alloc_error` if the arena is exhausted.
+    #[allow(clippy::mut_from_ref)]
+    #[cfg_attr(feature = "profile", track_caller)]
+    pub fn alloc_slice_fill_with<T>(&self, len: usize, mut f: impl FnMut(usize) -> T) -> &mut [T] {
+        let slot = self.alloc_array::<T>(len);
+        unsafe {
+            // values that were already written are leaked if `f` panics.
+            for i in 0..len {
+                slot.add(i).write(f(i));
+            }
+            slice::from_raw_parts_mut(slot, len)
+        }
+    }
+
+    /// copies `string` into the arena.
+    ///
+    /// # Panics
+    /// calls `handle_alloc_error` if the arena is exhausted.
+    #[allow(clippy::mut_from_ref)]
+    #[cfg_attr(feature = "profile", track_caller)]
+    pub fn alloc_str(&self, string: &str) -> &mut str {
+        let bytes = self.alloc_slice_copy(string.as_bytes());
+        unsafe { str::from_utf8_unchecked_mut(bytes) }
+    }
+
+    /// moves the items of `iter` into a slice in the arena.
+    /// the iterator has to know its length up front, so the slice can be reserved in one piece.
+    ///
+    /// # Panics
+    /// calls `handle_alloc_error` if the arena is exhausted and panics if the iterator yields fewer items than
+    /// its length promised.
+    #[allow(clippy::mut_from_ref)]
+    #[cfg_attr(feature = "profile", track_caller)]
+    pub fn alloc_from_iter<T, I>(&self, iter: I) -> &mut [T]
+    where
+        I: IntoIterator<Item = T>,
+        I::IntoIter: ExactSizeIterator,
+    {
+        let mut iter = iter.into_iter();
+        self.alloc_slice_fill_with(iter.len(), |_| iter.next().expect("iterator is shorter than its length"))
+    }
+
+    /// same as `alloc` but additionally registers the destructor of the value,
+    /// which is run when the arena is reset, rolled back below the value or dropped.
+    /// this allows storing owning types like `String` or `Vec` in the arena without leaking their memory.
+    /// destructors run in reverse allocation order.
+    ///
+    /// # Panics
+    /// calls `handle_alloc_error` if the arena is exhausted.
+    #[allow(clippy::mut_from_ref)]
+    #[cfg_attr(feature = "profile", track_caller)]
+    pub fn alloc_droppable<T: Send>(&self, value: T) -> &mut T {
+        if !mem::needs_drop::<T>() {
+            return self.alloc(value);
+        }
+
+        // the entry is placed right before the value in the same block, so a mark or rollback target is either
+        // below both or above both.
+        let (layout, value_offset) = Layout::new::<DropEntry>()
+            .extend(Layout::new::<T>())
+            .expect("value is too large");
+        let block = self.alloc_layout(layout);
+        let slot = unsafe { block.add(value_offset).cast::<T>() };
+        let entry = block.cast::<DropEntry>();
+        unsafe {
+            slot.write(value);
+            entry.write(DropEntry {
+                value: slot.cast(),
+                drop_fn: drop_value::<T>,
+                next: ptr::null_mut(),
+            });
+        }
+
+        let mut head = self.drop_list.load(Ordering::Relaxed);
+        loop {
+            unsafe { (*entry).next = head };
+            match self
+                .drop_list
+                .compare_exchange_weak(head, entry, Ordering::Release, Ordering::Relaxed)
+            {
+                Ok(_) => return unsafe { &mut *slot },
+                Err(current) => head = current,
+            }
+        }
+    }
+
+    /// reserves space for `len` values of type `T`.
+    #[cfg_attr(feature = "profile", track_caller)]
+    fn alloc_array<T>(&self, len: usize) -> *mut T {
+        let layout = Layout::array::<T>(len).expect("slice is too large");
+        self.alloc_layout(layout).cast()
+    }
+
+    #[cfg_attr(feature = "profile", track_caller)]
+    fn alloc_layout(&self, layout: Layout) -> *mut u8 {
+        match self.get_next_mem_slice(layout) {
+            Ok(slot) => slot.as_mut_ptr(),
+            Err(_) => alloc::alloc::handle_alloc_error(layout),
+        }
+    }
+
+    /// runs the registered destructors of every entry at or above `offset`, newest first.
+    /// must not be called while other threads allocate from the arena.
+    pub(crate) fn run_drops(&self, offset: usize) {
+        let base = self.mem_pool.as_mut_ptr().addr();
+
+        // concurrent pushes can link the entries out of address order, so the whole list is scanned.
+        let mut previous: *mut DropEntry = ptr::null_mut();
+        loop {
+            let entry = match previous.is_null() {
+                true => self.drop_list.load(Ordering::Acquire),
+                false => unsafe { (*previous).next },
+            };
+            if entry.is_null() {
+                return;
+            }
+            if entry.addr() - base < offset {
+                previous = entry;
+                continue;
+            }
+
+            // the entry is unlinked first, so a panicking destructor doesn't cause a double drop.
+            unsafe {
+                let DropEntry { value, drop_fn, next } = entry.read();
+                match previous.is_null() {
+                    true => self.drop_list.store(next, Ordering::Release),
+                    false => (*previous).next = next,
+                }
+                // the value may have been reborrowed from the memory pool since, e.g. by `Arena::get_mut`,
+                // which invalidates the pointer of the entry.
+                drop_fn(self.mem_pool.as_mut_ptr().with_addr(value.addr()));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::{
+        string::String,
+        sync::{Arc, Mutex},
+        vec::Vec,
+    };
+
+    use super::*;
+    use crate::ArenaAllocator;
+
+    enum Expr<'a> {
+        Number(u32),
+        Add(&'a Expr<'a>, &'a Expr<'a>),
+    }
+
+    fn eval(expr: &Expr) -> u32 {
+        match expr {
+            Expr::Number(number) => *number,
+            Expr::Add(lhs, rhs) => eval(lhs) + eval(rhs),
+        }
+    }
+
+    #[test]
+    fn alloc() {
+        let arena = Arena::new(256);
+        let one = arena.alloc(Expr::Number(1));
+        let two = arena.alloc_with(|| Expr::Number(2));
+        let sum = arena.alloc(Expr::Add(one, two));
+        assert_eq!(eval(arena.alloc(Expr::Add(sum, sum))), 6);
+
+        let counter = arena.alloc(0u64);
+        *counter += 1;
+        assert_eq!(*counter, 1);
+        assert_eq!(arena.live_allocations(), 5);
+    }
+
+    #[test]
+    fn slices() {
+        let arena = ArenaAllocator::new(Arena::new(256));
+        assert_eq!(arena.alloc_slice_copy(&[1u16, 2, 3]), &[1, 2, 3]);
+        assert_eq!(arena.alloc_slice_fill_with(4, |i| i * i), &[0, 1, 4, 9]);
+        assert_eq!(arena.alloc_from_iter((0..5u8).map(|i| i + 1)), &[1, 2, 3, 4, 5]);
+
+        let string = arena.alloc_str("arena");
+        string.make_ascii_uppercase();
+        assert_eq!(string, "ARENA");
+        assert!(arena.alloc_slice_copy::<u64>(&[]).is_empty());
+    }
+
+    #[test]
+    fn try_alloc() {
+        let arena = Arena::new_aligned(8, 4);
+        assert_eq!(*arena.try_alloc(7u32).unwrap(), 7);
+        assert_eq!(arena.try_alloc([0u8; 8]).unwrap_err().available, 4);
+        assert_eq!(arena.used_space(), 4);
+    }
+
+    struct Recorder(usize, Arc<Mutex<Vec<usize>>>);
+
+    impl Drop for Recorder {
+        fn drop(&mut self) {
+            self.1.lock().unwrap().push(self.0);
+        }
+    }
+
+    #[test]
+    fn alloc_droppable() {
+        let dropped = Arc::new(Mutex::new(Vec::new()));
+        let mut arena = Arena::new_aligned(1024, 8);
+
+        let name = arena.alloc_droppable(String::from("arena"));
+        name.push_str(" allocated");
+        assert_eq!(name, "arena allocated");
+
+        for i in 0..3 {
+            arena.alloc_droppable(Recorder(i, dropped.clone()));
+        }
+        arena.reset();
+        assert_eq!(*dropped.lock().unwrap(), [2, 1, 0]);
+
+        // values that don't need dropping aren't registered
+        arena.alloc_droppable(5u64);
+        assert_eq!(arena.used_space(), 8);
+
+        arena.alloc_droppable(Recorder(3, dropped.clone()));
+        {
+            let checkpoint = arena.checkpoint();
+            checkpoint.alloc_droppable(Recorder(4, dropped.clone()));
+        }
+        assert_eq!(*dropped.lock().unwrap(), [2, 1, 0, 4]);
+
+        drop(arena);
+        assert_eq!(*dropped.lock().unwrap(), [2, 1, 0, 4, 3]);
+    }
+
+    #[test]
+    fn rollback_with_interleaved_pushes() {
+        let dropped = Arc::new(Mutex::new(Vec::new()));
+        let mut arena = Arena::new_aligned(1024, 8);
+        arena.alloc_droppable(Recorder(0, dropped.clone()));
+        let mark = arena.mark();
+        arena.alloc_droppable(Recorder(1, dropped.clone()));
+        arena.alloc_droppable(Recorder(2, dropped.clone()));
+
+        // concurrent pushes can link an entry in front of entries with higher addresses
+        unsafe {
+            let second = arena.drop_list.load(Ordering::Relaxed);
+            let first = (*second).next;
+            let lowest = (*first).next;
+            (*first).next = ptr::null_mut();
+            (*lowest).next = second;
+            arena.drop_list.store(lowest, Ordering::Relaxed);
+        }
+
+        assert!(unsafe { arena.rollback_to(mark) });
+        assert_eq!(*dropped.lock().unwrap(), [2, 1]);
+        arena.reset();
+        assert_eq!(*dropped.lock().unwrap(), [2, 1, 0]);
+    }
+}
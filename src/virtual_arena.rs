@@ -0,0 +1,86 @@
+use std::{io, ops::Deref};
+
+use crate::{Arena, Checkpoint, Handle, MmapOptions};
+
+/// arena that reserves a large range of address space up front and only commits the pages just ahead of the offset.
+/// resetting decommits every page beyond the first commit step, which gives the memory back to the operating system
+/// while keeping the address space. this is built on `mmap` on unix and on `VirtualAlloc` on windows.
+/// the arena is only handed out as `&Arena`, so it can't be replaced by one that commits its pages differently.
+pub struct VirtualArena {
+    arena: Arena,
+}
+
+impl VirtualArena {
+    /// reserves `reserve` bytes of address space and commits it in steps of `commit_step` bytes.
+    /// both are rounded up to the page size.
+    pub fn new(reserve: usize, commit_step: usize) -> io::Result<Self> {
+        Ok(Self {
+            arena: Arena::new_mmap_with(
+                reserve,
+                MmapOptions::new().commit_step(commit_step).trim_on_reset(commit_step),
+            )?,
+        })
+    }
+
+    /// resets the arena and decommits every page beyond the first commit step.
+    pub fn reset(&mut self) {
+        self.arena.reset();
+    }
+
+    /// see `Arena::trim`.
+    pub fn trim(&mut self) -> io::Result<usize> {
+        self.arena.trim()
+    }
+
+    /// see `Arena::checkpoint`.
+    pub fn checkpoint(&mut self) -> Checkpoint<'_> {
+        self.arena.checkpoint()
+    }
+
+    /// see `Arena::scope`.
+    pub fn scope<R>(&mut self, f: impl FnOnce(&Arena) -> R) -> R {
+        self.arena.scope(f)
+    }
+
+    /// see `Arena::get_mut`.
+    pub fn get_mut<T>(&mut self, handle: Handle<T>) -> Option<&mut T> {
+        self.arena.get_mut(handle)
+    }
+}
+
+impl Deref for VirtualArena {
+    type Target = Arena;
+
+    fn deref(&self) -> &Self::Target {
+        &self.arena
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use core::alloc::Layout;
+
+    use super::*;
+    use crate::sys;
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn decommits_on_reset() {
+        let page_size = sys::page_size();
+        let mut arena = VirtualArena::new(1 << 30, page_size).unwrap();
+        assert_eq!(arena.committed(), page_size);
+
+        let slice = arena.get_next_mem_slice(Layout::array::<u8>(10 * page_size).unwrap()).unwrap();
+        unsafe { slice.as_mut_ptr().write_bytes(0xAA, 10 * page_size) };
+        assert_eq!(arena.committed(), 10 * page_size);
+
+        arena.reset();
+        assert_eq!(arena.committed(), page_size);
+
+        // decommitted pages are zeroed once they are committed again
+        #[cfg(not(feature = "debug-fill"))]
+        let slice = arena.get_next_mem_slice(Layout::array::<u8>(10 * page_size).unwrap()).unwrap();
+        #[cfg(not(feature = "debug-fill"))]
+        assert_eq!(unsafe { slice.as_ref() }[page_size..], vec![0; 9 * page_size]);
+    }
+}
@@ -0,0 +1,92 @@
+//! memory pools at the end of the wasm linear memory.
+
+use core::{
+    alloc::Layout,
+    arch::wasm32,
+    ptr::{self, NonNull},
+};
+
+use crate::{sync::Mutex, Arena, ArenaError, ArenaErrorKind, Backing};
+
+/// size of a page of wasm linear memory.
+pub const WASM_PAGE_SIZE: usize = 64 * 1024;
+
+impl Arena {
+    /// create a new arena at the end of the wasm linear memory that can grow up to `max_capacity` bytes.
+    /// `initial_capacity` bytes are grown right away and linear memory is grown in pages of 64 KiB as the offset
+    /// advances. growing fails with `OutOfSpace` once something else, e.g. the global allocator, grew linear memory
+    /// after the arena. both capacities are rounded up to the page size.
+    /// linear memory can't shrink, so the memory pool is not given back when the arena is dropped.
+    pub fn new_wasm(initial_capacity: usize, max_capacity: usize) -> Result<Self, ArenaError> {
+        let error = |kind| ArenaError {
+            layout: Layout::new::<u8>(),
+            available: 0,
+            kind,
+        };
+        let pages = |bytes: usize| {
+            bytes
+                .checked_next_multiple_of(WASM_PAGE_SIZE)
+                .ok_or_else(|| error(ArenaErrorKind::Overflow))
+        };
+
+        let committed = pages(initial_capacity)?;
+        let capacity = pages(max_capacity)?.max(committed);
+        let previous = wasm32::memory_grow::<0>(committed / WASM_PAGE_SIZE);
+        if previous == usize::MAX {
+            return Err(error(ArenaErrorKind::PoolAllocationFailed));
+        }
+
+        let base = NonNull::new(ptr::with_exposed_provenance_mut::<u8>(previous * WASM_PAGE_SIZE))
+            .ok_or_else(|| error(ArenaErrorKind::PoolAllocationFailed))?;
+        let mut arena = Self::from_mem_pool(
+            NonNull::slice_from_raw_parts(base, capacity),
+            Backing::Wasm {
+                committed: Mutex::new(committed),
+            },
+        );
+        arena.requested_capacity = max_capacity;
+        Ok(arena)
+    }
+
+    /// grows linear memory until at least the first `end` bytes of the memory pool exist.
+    pub(crate) fn grow_linear_memory(&self, committed: &Mutex<usize>, end: usize) -> Result<(), ArenaErrorKind> {
+        let mut committed = committed.lock();
+        if end <= *committed {
+            return Ok(());
+        }
+
+        // only the end of linear memory can grow, so the pool can't grow once something was placed after it.
+        let committed_end = self.mem_pool.as_mut_ptr().addr() + *committed;
+        if wasm32::memory_size::<0>() * WASM_PAGE_SIZE != committed_end {
+            return Err(ArenaErrorKind::OutOfSpace);
+        }
+
+        let target = end.next_multiple_of(WASM_PAGE_SIZE).min(self.capacity());
+        if wasm32::memory_grow::<0>((target - *committed) / WASM_PAGE_SIZE) != committed_end / WASM_PAGE_SIZE {
+            return Err(ArenaErrorKind::OutOfSpace);
+        }
+        *committed = target;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn grows_linear_memory() {
+        let arena = Arena::new_wasm(0, 4 * WASM_PAGE_SIZE).unwrap();
+        assert_eq!(arena.committed(), 0);
+
+        arena.get_next_mem_slice(Layout::new::<[u8; 100]>()).unwrap();
+        assert_eq!(arena.committed(), WASM_PAGE_SIZE);
+        arena
+            .get_next_mem_slice(Layout::array::<u8>(2 * WASM_PAGE_SIZE).unwrap())
+            .unwrap();
+        assert_eq!(arena.committed(), 3 * WASM_PAGE_SIZE);
+        assert!(arena
+            .get_next_mem_slice(Layout::array::<u8>(2 * WASM_PAGE_SIZE).unwrap())
+            .is_err());
+    }
+}